@@ -0,0 +1,62 @@
+#![feature(test)]
+
+extern crate test;
+
+use argon2::{Algorithm, Argon2, Block, Params, Version};
+use test::Bencher;
+
+fn bench_argon2(bh: &mut Bencher, algorithm: Algorithm, m_cost: u32, t_cost: u32) {
+    bench_argon2_lanes(bh, algorithm, m_cost, t_cost, 1);
+}
+
+fn bench_argon2_lanes(
+    bh: &mut Bencher,
+    algorithm: Algorithm,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) {
+    let params = Params::new(m_cost, t_cost, p_cost, None).unwrap();
+    let argon2 = Argon2::new(algorithm, Version::V0x13, params);
+    let password = b"my secure password";
+    let salt = b"salty salt";
+    let mut buf = [0u8; 32];
+    bh.iter(|| {
+        argon2.hash_password_into(password, salt, &mut buf).unwrap();
+        test::black_box(&buf);
+    });
+}
+
+#[bench]
+pub fn argon2d_m4096_t3(bh: &mut Bencher) {
+    bench_argon2(bh, Algorithm::Argon2d, 4096, 3);
+}
+
+#[bench]
+pub fn argon2i_m4096_t3(bh: &mut Bencher) {
+    bench_argon2(bh, Algorithm::Argon2i, 4096, 3);
+}
+
+#[bench]
+pub fn argon2id_m4096_t3(bh: &mut Bencher) {
+    bench_argon2(bh, Algorithm::Argon2id, 4096, 3);
+}
+
+/// Many more lanes than cores, which are filled in parallel with the
+/// `parallel` feature
+#[bench]
+pub fn argon2id_m4096_t3_p64(bh: &mut Bencher) {
+    bench_argon2_lanes(bh, Algorithm::Argon2id, 4096, 3, 64);
+}
+
+#[bench]
+pub fn compress(bh: &mut Bencher) {
+    let rhs = Block::default();
+    let lhs = Block::default();
+    bh.iter(|| {
+        test::black_box(Block::compress(
+            test::black_box(&rhs),
+            test::black_box(&lhs),
+        ))
+    });
+}
@@ -0,0 +1,227 @@
+//! Reference block sequences of data-independent addressing.
+
+use crate::{
+    instance::{first_index, reference_position, AddressGenerator, Position},
+    Argon2, Block, SYNC_POINTS,
+};
+use core::{fmt, iter::FusedIterator};
+
+/// Iterator over the reference blocks of a segment, as `(lane, index)`
+/// pairs, where `index` is the position of the block within its lane.
+///
+/// Created with [`Argon2::reference_blocks`].
+pub struct ReferenceBlocks {
+    /// Generator of the pseudo-random values
+    addresses: AddressGenerator,
+
+    /// Position of the next block to compute
+    position: Position,
+
+    /// Number of lanes
+    lanes: u32,
+
+    /// Number of blocks per lane
+    lane_length: u32,
+
+    /// Number of blocks per segment
+    segment_length: u32,
+}
+
+impl Iterator for ReferenceBlocks {
+    type Item = (u32, u32);
+
+    fn next(&mut self) -> Option<(u32, u32)> {
+        if self.position.index >= self.segment_length {
+            return None;
+        }
+
+        let pseudo_rand = self.addresses.pseudo_rand(self.position.index);
+        let reference = reference_position(
+            self.lanes,
+            self.lane_length,
+            self.segment_length,
+            self.position,
+            pseudo_rand,
+        );
+
+        self.position.index += 1;
+        Some(reference)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.segment_length.saturating_sub(self.position.index) as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for ReferenceBlocks {}
+
+impl FusedIterator for ReferenceBlocks {}
+
+impl fmt::Debug for ReferenceBlocks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReferenceBlocks")
+            .field("pass", &self.position.pass)
+            .field("lane", &self.position.lane)
+            .field("slice", &self.position.slice)
+            .field("index", &self.position.index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Argon2<'_> {
+    /// Get the reference blocks used to compute the blocks of the segment at
+    /// the given `pass`, `lane` and `slice`, without filling any memory.
+    ///
+    /// The blocks are yielded in the order in which a real run references
+    /// them, i.e. for every block of the segment which is computed from a
+    /// reference block, including the blocks filled with the initial hash
+    /// only for the first slice of the first pass. Only the compressions
+    /// which generate the addresses are computed.
+    ///
+    /// Returns `None` if the segment uses data-dependent addressing, i.e.
+    /// with [`Algorithm::Argon2d`][`crate::Algorithm::Argon2d`] and for all
+    /// but the first half of the first pass with
+    /// [`Algorithm::Argon2id`][`crate::Algorithm::Argon2id`], as these
+    /// references depend on the memory contents. Also returns `None` if
+    /// `pass`, `lane` or `slice` is out of range for the parameters; there are
+    /// [`SYNC_POINTS`] slices per lane.
+    pub fn reference_blocks(&self, pass: u32, lane: u32, slice: u32) -> Option<ReferenceBlocks> {
+        if pass >= self.params.t_cost() || lane >= self.params.lanes() || slice >= SYNC_POINTS {
+            return None;
+        }
+
+        if !self
+            .algorithm
+            .uses_data_independent_addressing(pass as usize, slice as usize)
+        {
+            return None;
+        }
+
+        let mut position = Position {
+            pass,
+            lane,
+            slice,
+            index: 0,
+        };
+        position.index = first_index(position);
+
+        let addresses = AddressGenerator::new(
+            Block::compress_fn(),
+            position,
+            self.params.block_count(),
+            self.params.t_cost(),
+            self.algorithm,
+        );
+
+        Some(ReferenceBlocks {
+            addresses,
+            position,
+            lanes: self.params.lanes(),
+            lane_length: self.params.lane_length(),
+            segment_length: self.params.segment_length(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Algorithm, Argon2, Params, Version, SYNC_POINTS};
+
+    #[test]
+    fn segment_bounds() {
+        let params = Params::new(64, 2, 2, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2i, Version::V0x13, params);
+        let lane_length = 32;
+        let segment_length = 8;
+
+        for pass in 0..2 {
+            for lane in 0..2 {
+                for slice in 0..SYNC_POINTS {
+                    let references = argon2.reference_blocks(pass, lane, slice).unwrap();
+                    let skipped = if pass == 0 && slice == 0 { 2 } else { 0 };
+                    assert_eq!(references.len(), segment_length - skipped);
+
+                    let start = slice * segment_length as u32 + skipped as u32;
+                    for (i, (ref_lane, ref_index)) in references.enumerate() {
+                        let index = start + i as u32;
+                        assert!(ref_lane < 2);
+                        assert!(ref_index < lane_length);
+
+                        if pass == 0 {
+                            // Only previously computed blocks can be referenced
+                            if ref_lane == lane {
+                                assert!(ref_index < index);
+                            } else {
+                                assert!(ref_index < slice * segment_length as u32);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        assert!(argon2.reference_blocks(2, 0, 0).is_none());
+        assert!(argon2.reference_blocks(0, 2, 0).is_none());
+        assert!(argon2.reference_blocks(0, 0, SYNC_POINTS).is_none());
+    }
+
+    #[test]
+    fn data_dependent_segments() {
+        let params = Params::new(64, 2, 2, None).unwrap();
+
+        let argon2d = Argon2::new(Algorithm::Argon2d, Version::V0x13, params.clone());
+        assert!(argon2d.reference_blocks(0, 0, 0).is_none());
+
+        let argon2id = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        assert!(argon2id.reference_blocks(0, 1, 1).is_some());
+        assert!(argon2id.reference_blocks(0, 1, 2).is_none());
+        assert!(argon2id.reference_blocks(1, 0, 0).is_none());
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn matches_real_run() {
+        use crate::AccessStats;
+
+        let params = Params::new(64, 3, 2, None).unwrap();
+        let lane_length = params.lane_length();
+
+        for alg in [Algorithm::Argon2i, Algorithm::Argon2id] {
+            let argon2 = Argon2::new(alg, Version::V0x13, params.clone());
+            let mut stats = AccessStats::new();
+            let mut out = [0u8; 32];
+            argon2
+                .hash_password_into_with_stats(b"password", b"somesalt", &mut out, &mut stats)
+                .unwrap();
+
+            // Argon2id only uses data-independent addressing for the first
+            // two slices of the first pass
+            let (passes, slices) = match alg {
+                Algorithm::Argon2i => (3, SYNC_POINTS),
+                _ => (1, SYNC_POINTS / 2),
+            };
+
+            let mut references = [0u64; 64];
+            for pass in 0..passes {
+                for lane in 0..2 {
+                    for slice in 0..slices {
+                        for (ref_lane, ref_index) in
+                            argon2.reference_blocks(pass, lane, slice).unwrap()
+                        {
+                            references[(ref_lane * lane_length + ref_index) as usize] += 1;
+                        }
+                    }
+                }
+            }
+
+            if alg == Algorithm::Argon2i {
+                assert_eq!(&references[..], stats.references());
+            } else {
+                for (dry_run, real) in references.iter().zip(stats.references()) {
+                    assert!(dry_run <= real);
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 //! Argon2 algorithms (e.g. Argon2d, Argon2i, Argon2id).
 
-use crate::{Error, Result};
+use crate::{Error, Result, SYNC_POINTS};
 use core::{
     fmt::{self, Display},
     str::FromStr,
@@ -25,7 +25,12 @@ pub const ARGON2I_IDENT: Ident<'_> = Ident::new_unwrap("argon2i");
 pub const ARGON2ID_IDENT: Ident<'_> = Ident::new_unwrap("argon2id");
 
 /// Argon2 primitive type: variants of the algorithm.
-#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+///
+/// Algorithms are ordered by their numeric type as used in the initial
+/// hash, i.e. `Argon2d < Argon2i < Argon2id`. This ordering is only meant
+/// for sorting, e.g. as keys of a `BTreeMap`, and says nothing about their
+/// relative strength.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
 pub enum Algorithm {
     /// Optimizes against GPU cracking attacks but vulnerable to side-channels.
     ///
@@ -62,6 +67,11 @@ impl Algorithm {
         id.as_ref().parse()
     }
 
+    /// Get all Argon2 [`Algorithm`]s.
+    pub const fn all() -> [Self; 3] {
+        [Algorithm::Argon2d, Algorithm::Argon2i, Algorithm::Argon2id]
+    }
+
     /// Get the identifier string for this PBKDF2 [`Algorithm`].
     pub fn as_str(&self) -> &str {
         match self {
@@ -82,9 +92,33 @@ impl Algorithm {
         }
     }
 
+    /// Get the numeric identifier of this [`Algorithm`] which is hashed
+    /// into the initial hash: 0 for Argon2d, 1 for Argon2i and 2 for
+    /// Argon2id, as in the reference implementation.
+    pub const fn as_u32(&self) -> u32 {
+        *self as u32
+    }
+
+    /// Does this [`Algorithm`] use data-independent addressing for the
+    /// segments of the given slice of the given pass (both counted from 0)?
+    ///
+    /// With data-independent addressing, the reference blocks are computed
+    /// from a counter rather than from the contents of the previous block,
+    /// so the memory access pattern doesn't depend on the password. This is
+    /// always the case for Argon2i, for the first half of the first pass
+    /// (the first [`SYNC_POINTS`] / 2 slices) for Argon2id, and never for
+    /// Argon2d.
+    pub fn uses_data_independent_addressing(&self, pass: usize, slice: usize) -> bool {
+        match self {
+            Algorithm::Argon2d => false,
+            Algorithm::Argon2i => true,
+            Algorithm::Argon2id => pass == 0 && slice < SYNC_POINTS as usize / 2,
+        }
+    }
+
     /// Serialize primitive type as little endian bytes
     pub(crate) fn to_le_bytes(self) -> [u8; 4] {
-        (self as u32).to_le_bytes()
+        self.as_u32().to_le_bytes()
     }
 }
 
@@ -103,13 +137,12 @@ impl Display for Algorithm {
 impl FromStr for Algorithm {
     type Err = Error;
 
+    /// Parse an [`Algorithm`] from its identifier, ignoring ASCII case.
     fn from_str(s: &str) -> Result<Algorithm> {
-        match s {
-            "argon2d" => Ok(Algorithm::Argon2d),
-            "argon2i" => Ok(Algorithm::Argon2i),
-            "argon2id" => Ok(Algorithm::Argon2id),
-            _ => Err(Error::AlgorithmInvalid),
-        }
+        Self::all()
+            .into_iter()
+            .find(|alg| alg.as_str().eq_ignore_ascii_case(s))
+            .ok_or(Error::AlgorithmInvalid)
     }
 }
 
@@ -135,3 +168,84 @@ impl<'a> TryFrom<Ident<'a>> for Algorithm {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Algorithm;
+    use crate::Error;
+
+    #[test]
+    fn all() {
+        assert_eq!(
+            Algorithm::all(),
+            [Algorithm::Argon2d, Algorithm::Argon2i, Algorithm::Argon2id]
+        );
+
+        for algorithm in Algorithm::all() {
+            assert_eq!(algorithm.as_str().parse(), Ok(algorithm));
+        }
+    }
+
+    #[test]
+    fn ord() {
+        assert!(Algorithm::Argon2d < Algorithm::Argon2i);
+        assert!(Algorithm::Argon2i < Algorithm::Argon2id);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hash() {
+        let set: std::collections::HashSet<_> = Algorithm::all()
+            .into_iter()
+            .chain(Algorithm::all())
+            .collect();
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn as_u32() {
+        assert_eq!(Algorithm::Argon2d.as_u32(), 0);
+        assert_eq!(Algorithm::Argon2i.as_u32(), 1);
+        assert_eq!(Algorithm::Argon2id.as_u32(), 2);
+    }
+
+    #[test]
+    fn uses_data_independent_addressing() {
+        for pass in 0..3 {
+            for slice in 0..4 {
+                assert!(!Algorithm::Argon2d.uses_data_independent_addressing(pass, slice));
+                assert!(Algorithm::Argon2i.uses_data_independent_addressing(pass, slice));
+                assert_eq!(
+                    Algorithm::Argon2id.uses_data_independent_addressing(pass, slice),
+                    pass == 0 && slice < 2
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("argon2d".parse(), Ok(Algorithm::Argon2d));
+        assert_eq!("argon2i".parse(), Ok(Algorithm::Argon2i));
+        assert_eq!("argon2id".parse(), Ok(Algorithm::Argon2id));
+        assert_eq!("Argon2ID".parse(), Ok(Algorithm::Argon2id));
+        assert_eq!("argon2".parse::<Algorithm>(), Err(Error::AlgorithmInvalid));
+        assert_eq!(
+            "argon2idd".parse::<Algorithm>(),
+            Err(Error::AlgorithmInvalid)
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn display() {
+        use alloc::string::ToString;
+
+        for algorithm in Algorithm::all() {
+            assert_eq!(algorithm.to_string(), algorithm.as_str());
+            assert_eq!(algorithm.to_string().parse(), Ok(algorithm));
+        }
+
+        assert_eq!(Algorithm::Argon2id.to_string(), "argon2id");
+    }
+}
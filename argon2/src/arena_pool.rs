@@ -0,0 +1,233 @@
+//! Pool of memory arenas shared between threads.
+
+use crate::{Block, Params, Result};
+use alloc::vec::Vec;
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+use std::sync::{Mutex, PoisonError};
+use zeroize::Zeroize;
+
+/// Pool of pre-allocated memory arenas, each sized for the given
+/// [`Params`], which can be checked out by multiple threads.
+///
+/// This allows sharing one [`Argon2`][`crate::Argon2`] context between a
+/// fixed number of worker threads without allocating memory for every hash:
+/// each thread checks out an [`ArenaGuard`] and passes it to
+/// [`Argon2::hash_password_into_with_memory`][`crate::Argon2::hash_password_into_with_memory`].
+/// The arena is returned to the pool when the guard is dropped.
+///
+/// Returned arenas are zeroized by default. This can be disabled with
+/// [`ArenaPool::wipe_on_return`], e.g. if the arenas are only used with
+/// the hashing functions, which already zeroize the memory they used when
+/// the `zeroize` feature is enabled.
+#[cfg_attr(docsrs, doc(cfg(all(feature = "std", feature = "zeroize"))))]
+pub struct ArenaPool {
+    /// Arenas which aren't checked out
+    arenas: Mutex<Vec<Vec<Block>>>,
+
+    /// Number of blocks of every arena
+    block_count: usize,
+
+    /// Whether to zeroize arenas when they are returned
+    wipe: bool,
+}
+
+impl ArenaPool {
+    /// Allocate `arenas` memory arenas for hashing with the given
+    /// [`Params`].
+    pub fn new(params: &Params, arenas: usize) -> Result<Self> {
+        params.checked_memory_bytes()?;
+        crate::policy::check_global_max_memory(params.memory_bytes())?;
+
+        let block_count = params.block_count();
+        let arenas = (0..arenas)
+            .map(|_| vec![Block::default(); block_count])
+            .collect();
+
+        Ok(Self {
+            arenas: Mutex::new(arenas),
+            block_count,
+            wipe: true,
+        })
+    }
+
+    /// Set whether arenas are zeroized when they are returned to the pool.
+    pub fn wipe_on_return(mut self, wipe: bool) -> Self {
+        self.wipe = wipe;
+        self
+    }
+
+    /// Check out an arena, or return `None` if all arenas are in use.
+    pub fn checkout(&self) -> Option<ArenaGuard<'_>> {
+        let blocks = self.lock().pop()?;
+        Some(ArenaGuard { pool: self, blocks })
+    }
+
+    /// Number of arenas which are currently available.
+    pub fn available(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Number of blocks of every arena, i.e. [`Params::block_count`].
+    pub fn block_count(&self) -> usize {
+        self.block_count
+    }
+
+    /// Lock the available arenas.
+    ///
+    /// The list of arenas is consistent at all times, so it's still usable
+    /// after a thread panicked while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Vec<Block>>> {
+        self.arenas.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for ArenaPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArenaPool")
+            .field("available", &self.available())
+            .field("block_count", &self.block_count)
+            .field("wipe", &self.wipe)
+            .finish()
+    }
+}
+
+/// Memory arena checked out from an [`ArenaPool`], which is returned to the
+/// pool when dropped.
+#[cfg_attr(docsrs, doc(cfg(all(feature = "std", feature = "zeroize"))))]
+pub struct ArenaGuard<'a> {
+    /// Pool to return the arena to
+    pool: &'a ArenaPool,
+
+    /// Memory blocks
+    blocks: Vec<Block>,
+}
+
+impl Deref for ArenaGuard<'_> {
+    type Target = [Block];
+
+    fn deref(&self) -> &[Block] {
+        &self.blocks
+    }
+}
+
+impl DerefMut for ArenaGuard<'_> {
+    fn deref_mut(&mut self) -> &mut [Block] {
+        &mut self.blocks
+    }
+}
+
+impl AsRef<[Block]> for ArenaGuard<'_> {
+    fn as_ref(&self) -> &[Block] {
+        &self.blocks
+    }
+}
+
+impl AsMut<[Block]> for ArenaGuard<'_> {
+    fn as_mut(&mut self) -> &mut [Block] {
+        &mut self.blocks
+    }
+}
+
+impl fmt::Debug for ArenaGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArenaGuard")
+            .field("blocks", &self.blocks.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for ArenaGuard<'_> {
+    fn drop(&mut self) {
+        if self.pool.wipe {
+            for block in self.blocks.iter_mut() {
+                block.zeroize();
+            }
+        }
+
+        let blocks = core::mem::take(&mut self.blocks);
+        self.pool.lock().push(blocks);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArenaPool;
+    use crate::{Algorithm, Argon2, Params, Version};
+    use alloc::vec::Vec;
+    use std::{sync::Arc, thread};
+
+    #[test]
+    fn hash_with_arenas() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone());
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(b"password", b"somesalt", &mut expected)
+            .unwrap();
+
+        let pool = Arc::new(ArenaPool::new(&params, 2).unwrap());
+        assert_eq!(pool.block_count(), 32);
+
+        let threads: Vec<_> = (0..2)
+            .map(|_| {
+                let pool = Arc::clone(&pool);
+                let argon2 = argon2.clone();
+
+                thread::spawn(move || {
+                    let mut arena = pool.checkout().unwrap();
+
+                    for _ in 0..2 {
+                        let mut out = [0u8; 32];
+                        argon2
+                            .hash_password_into_with_memory(
+                                b"password",
+                                b"somesalt",
+                                &mut out,
+                                &mut arena,
+                            )
+                            .unwrap();
+                        assert_eq!(out, expected);
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(pool.available(), 2);
+    }
+
+    #[test]
+    fn checkout_and_return() {
+        let params = Params::new(32, 1, 1, None).unwrap();
+        let pool = ArenaPool::new(&params, 1).unwrap();
+
+        let mut arena = pool.checkout().unwrap();
+        assert_eq!(arena.len(), 32);
+        assert_eq!(pool.available(), 0);
+        assert!(pool.checkout().is_none());
+
+        arena[0][0] = 1;
+        drop(arena);
+        assert_eq!(pool.available(), 1);
+
+        // The arena has been wiped
+        let arena = pool.checkout().unwrap();
+        assert!(arena
+            .iter()
+            .all(|block| block.iter().all(|&word| word == 0)));
+        drop(arena);
+
+        let pool = pool.wipe_on_return(false);
+        let mut arena = pool.checkout().unwrap();
+        arena[0][0] = 1;
+        drop(arena);
+        assert_eq!(pool.checkout().unwrap()[0][0], 1);
+    }
+}
@@ -0,0 +1,83 @@
+//! Hashing on the blocking thread pool of a tokio runtime.
+
+use crate::{Argon2, Error, Params, Result};
+use alloc::vec::Vec;
+
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+impl Argon2<'static> {
+    /// Hash a password and salt on tokio's blocking thread pool, returning
+    /// the raw hash.
+    ///
+    /// Hashing keeps a thread busy for as long as the parameters demand,
+    /// which would stall other tasks if done on an async executor thread.
+    /// This runs it with [`tokio::task::spawn_blocking`] instead, and must be
+    /// called from within a tokio runtime.
+    ///
+    /// The spawned task must own everything it uses, so the context is
+    /// cloned (which is cheap, and why it needs a `'static` secret if any)
+    /// and the password and salt are taken by value. The output is
+    /// [`Params::output_len`] bytes long, or [`Params::DEFAULT_OUTPUT_LEN`]
+    /// if not set. When the `zeroize` feature is enabled, the password is
+    /// zeroized after hashing.
+    ///
+    /// Returns [`Error::Cancelled`] if the runtime is shut down before
+    /// hashing completes.
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    pub async fn hash_password_async(&self, pwd: Vec<u8>, salt: Vec<u8>) -> Result<Vec<u8>> {
+        let argon2 = self.clone();
+        let output_len = self
+            .params
+            .output_len()
+            .unwrap_or(Params::DEFAULT_OUTPUT_LEN);
+
+        let task = tokio::task::spawn_blocking(move || {
+            #[allow(unused_mut)]
+            let mut pwd = pwd;
+            let mut out = vec![0u8; output_len];
+            let result = argon2.hash_password_into(&pwd, &salt, &mut out);
+
+            #[cfg(feature = "zeroize")]
+            pwd.zeroize();
+
+            result.map(|()| out)
+        });
+
+        match task.await {
+            Ok(result) => result,
+            Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+            Err(_) => Err(Error::Cancelled),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Algorithm, Argon2, Error, Params, Version};
+
+    #[test]
+    fn hash_password_async() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(b"password", b"somesalt", &mut expected)
+            .unwrap();
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let hash = runtime
+            .block_on(argon2.hash_password_async(b"password".to_vec(), b"somesalt".to_vec()))
+            .unwrap();
+        assert_eq!(hash, expected);
+
+        assert_eq!(
+            runtime.block_on(argon2.hash_password_async(b"password".to_vec(), b"short".to_vec())),
+            Err(Error::SaltTooShort)
+        );
+    }
+}
@@ -1,5 +1,23 @@
 //! Argon2 memory block functions
 
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    any(feature = "std", target_feature = "avx2")
+))]
+mod avx2;
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    any(feature = "std", target_feature = "sse2")
+))]
+mod sse2;
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+mod neon;
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod simd128;
+
 use core::{
     num::Wrapping,
     ops::{BitXor, BitXorAssign, Index, IndexMut},
@@ -9,13 +27,24 @@ use core::{
 #[cfg(feature = "zeroize")]
 use zeroize::Zeroize;
 
+/// Implementation of the compression function `G`, see [`Block::compress_fn`].
+pub(crate) type CompressFn = fn(&Block, &Block) -> Block;
+
 /// Structure for the (1KB) memory block implemented as 128 64-bit words.
+///
+/// The memory passed to
+/// [`Argon2::hash_password_into_with_memory`][`crate::Argon2::hash_password_into_with_memory`]
+/// is a slice of blocks, which can be inspected with [`Block::as_bytes`]
+/// after hashing, e.g. to compare intermediate values with another
+/// implementation. It is zeroized after hashing when the `zeroize` feature
+/// is enabled.
 #[derive(Copy, Clone, Debug)]
+#[repr(transparent)]
 pub struct Block([u64; Self::SIZE / 8]);
 
 impl Default for Block {
     fn default() -> Self {
-        Self([0u64; Self::SIZE / 8])
+        Self::zero()
     }
 }
 
@@ -23,6 +52,67 @@ impl Block {
     /// Memory block size in bytes
     pub const SIZE: usize = 1024;
 
+    /// Create a block with all bits set to zero.
+    pub const fn zero() -> Self {
+        Self([0u64; Self::SIZE / 8])
+    }
+
+    /// Get the contents of the block as the 64-bit words the algorithm
+    /// operates on.
+    pub fn words(&self) -> &[u64; Self::SIZE / 8] {
+        &self.0
+    }
+
+    /// Compute the XOR of this block and `other`.
+    ///
+    /// Same as the `^` operator, without copying the operands.
+    pub fn xor(&self, other: &Block) -> Block {
+        let mut res = *self;
+        for (a, b) in res.iter_mut().zip(other.iter()) {
+            *a ^= *b;
+        }
+        res
+    }
+
+    /// Compute the compression function `G` of the Argon2 specification,
+    /// which combines two blocks into a new one with a permutation based on
+    /// the BLAKE2b round function.
+    ///
+    /// This is the primitive every new memory block is computed with, so it
+    /// determines the throughput of hashing. It uses the fastest
+    /// implementation available on the current CPU, see
+    /// [`Block::compress_fn`].
+    pub fn compress(rhs: &Self, lhs: &Self) -> Self {
+        Self::compress_fn()(rhs, lhs)
+    }
+
+    /// Get the contents of the block as bytes.
+    ///
+    /// The 64-bit words are in native byte order, so on little-endian targets
+    /// this is the same serialization as used by the reference
+    /// implementation. Use [`Block::to_le_bytes`] for a serialization which
+    /// doesn't depend on the target.
+    #[allow(unsafe_code)]
+    pub fn as_bytes(&self) -> &[u8; Self::SIZE] {
+        // SAFETY: `Block` is a `repr(transparent)` array of `Self::SIZE` bytes,
+        // `u8` has no alignment requirement and any byte is a valid `u8`
+        unsafe { &*(self.0.as_ptr() as *const [u8; Self::SIZE]) }
+    }
+
+    /// Serialize the block as bytes, with the 64-bit words in little-endian
+    /// byte order as specified by RFC 9106.
+    ///
+    /// Unlike [`Block::as_bytes`], this is the same on all targets.
+    pub fn to_le_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+
+        for (chunk, word) in bytes.chunks_exact_mut(8).zip(self.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+
+        bytes
+    }
+
     /// Load a block from a block-sized byte slice
     pub(crate) fn load(&mut self, input: &[u8]) {
         debug_assert_eq!(input.len(), Block::SIZE);
@@ -42,20 +132,61 @@ impl Block {
         self.0.iter_mut()
     }
 
-    /// Function fills a new memory block and optionally XORs the old block over the new one.
-    // TODO(tarcieri): optimized implementation (i.e. from opt.c instead of ref.c)
-    pub(crate) fn fill_block(&mut self, prev_block: Block, ref_block: Block, with_xor: bool) {
-        let mut block_r = ref_block ^ prev_block;
-        let mut block_tmp = block_r;
+    /// Select the fastest implementation of the compression function `G`
+    /// available on the current CPU.
+    ///
+    /// On x86/x86-64 with the `std` feature enabled, AVX2 and SSE2 support
+    /// is detected at runtime. Otherwise the implementation is selected from
+    /// the target features enabled at compile time.
+    ///
+    /// `G` XORs `rhs` and `lhs`, applies the BLAKE2b based permutation `P`
+    /// to the result and XORs it with the permutation output.
+    #[allow(unreachable_code, unsafe_code)]
+    pub(crate) fn compress_fn() -> CompressFn {
+        #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "std"))]
+        {
+            if std::is_x86_feature_detected!("avx2") {
+                // SAFETY: AVX2 support was detected above
+                return |rhs, lhs| unsafe { avx2::compress(rhs, lhs) };
+            }
 
-        // Now block_r = ref_block + prev_block and block_tmp = ref_block + prev_block
-        if with_xor {
-            // Saving the next block contents for XOR over
-            block_tmp ^= *self;
-            // Now block_r = ref_block + prev_block and
-            // block_tmp = ref_block + prev_block + next_block
+            if std::is_x86_feature_detected!("sse2") {
+                // SAFETY: SSE2 support was detected above
+                return |rhs, lhs| unsafe { sse2::compress(rhs, lhs) };
+            }
         }
 
+        #[cfg(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            not(feature = "std"),
+            target_feature = "avx2"
+        ))]
+        // SAFETY: AVX2 is enabled for the target
+        return |rhs, lhs| unsafe { avx2::compress(rhs, lhs) };
+
+        #[cfg(all(
+            any(target_arch = "x86", target_arch = "x86_64"),
+            not(feature = "std"),
+            target_feature = "sse2",
+            not(target_feature = "avx2")
+        ))]
+        // SAFETY: SSE2 is enabled for the target
+        return |rhs, lhs| unsafe { sse2::compress(rhs, lhs) };
+
+        #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+        return neon::compress;
+
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        return simd128::compress;
+
+        Self::compress_soft
+    }
+
+    /// Portable implementation of the compression function.
+    fn compress_soft(rhs: &Self, lhs: &Self) -> Self {
+        let block_r = *rhs ^ *lhs;
+        let mut block_q = block_r;
+
         /// Note: designed by the Lyra PHC team
         fn blake2_mult(x: u64, y: u64) -> u64 {
             let m = 0xFFFFFFFF;
@@ -97,22 +228,22 @@ impl Block {
         // (16, 17, ..31)... finally (112, 113, ...127)
         for i in 0..8 {
             blake2_round!(
-                block_r[16 * i],
-                block_r[16 * i + 1],
-                block_r[16 * i + 2],
-                block_r[16 * i + 3],
-                block_r[16 * i + 4],
-                block_r[16 * i + 5],
-                block_r[16 * i + 6],
-                block_r[16 * i + 7],
-                block_r[16 * i + 8],
-                block_r[16 * i + 9],
-                block_r[16 * i + 10],
-                block_r[16 * i + 11],
-                block_r[16 * i + 12],
-                block_r[16 * i + 13],
-                block_r[16 * i + 14],
-                block_r[16 * i + 15]
+                block_q[16 * i],
+                block_q[16 * i + 1],
+                block_q[16 * i + 2],
+                block_q[16 * i + 3],
+                block_q[16 * i + 4],
+                block_q[16 * i + 5],
+                block_q[16 * i + 6],
+                block_q[16 * i + 7],
+                block_q[16 * i + 8],
+                block_q[16 * i + 9],
+                block_q[16 * i + 10],
+                block_q[16 * i + 11],
+                block_q[16 * i + 12],
+                block_q[16 * i + 13],
+                block_q[16 * i + 14],
+                block_q[16 * i + 15]
             );
         }
 
@@ -120,26 +251,26 @@ impl Block {
         // (2, 3, 18, 19, ..., 114, 115).. finally (14, 15, 30, 31, ..., 126, 127)
         for i in 0..8 {
             blake2_round!(
-                block_r[2 * i],
-                block_r[2 * i + 1],
-                block_r[2 * i + 16],
-                block_r[2 * i + 17],
-                block_r[2 * i + 32],
-                block_r[2 * i + 33],
-                block_r[2 * i + 48],
-                block_r[2 * i + 49],
-                block_r[2 * i + 64],
-                block_r[2 * i + 65],
-                block_r[2 * i + 80],
-                block_r[2 * i + 81],
-                block_r[2 * i + 96],
-                block_r[2 * i + 97],
-                block_r[2 * i + 112],
-                block_r[2 * i + 113]
+                block_q[2 * i],
+                block_q[2 * i + 1],
+                block_q[2 * i + 16],
+                block_q[2 * i + 17],
+                block_q[2 * i + 32],
+                block_q[2 * i + 33],
+                block_q[2 * i + 48],
+                block_q[2 * i + 49],
+                block_q[2 * i + 64],
+                block_q[2 * i + 65],
+                block_q[2 * i + 80],
+                block_q[2 * i + 81],
+                block_q[2 * i + 96],
+                block_q[2 * i + 97],
+                block_q[2 * i + 112],
+                block_q[2 * i + 113]
             );
         }
 
-        *self = block_tmp ^ block_r;
+        block_q ^ block_r
     }
 }
 
@@ -181,3 +312,104 @@ impl Zeroize for Block {
         self.0.zeroize();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Block;
+
+    /// Fill a block with deterministic, non-trivial contents.
+    fn example_block(seed: u64) -> Block {
+        let mut block = Block::default();
+        let mut state = seed;
+
+        for word in block.iter_mut() {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            *word = state;
+        }
+
+        block
+    }
+
+    #[test]
+    fn as_bytes() {
+        let block = example_block(1);
+        let bytes = block.as_bytes();
+
+        for (chunk, word) in bytes.chunks(8).zip(block.iter()) {
+            assert_eq!(chunk, word.to_ne_bytes());
+        }
+    }
+
+    #[test]
+    fn zero() {
+        assert!(Block::zero().words().iter().all(|&word| word == 0));
+        assert_eq!(Block::default().words(), Block::zero().words());
+    }
+
+    #[test]
+    fn words() {
+        let block = example_block(1);
+        let words = block.words();
+
+        for (i, word) in words.iter().enumerate() {
+            assert_eq!(*word, block[i]);
+        }
+    }
+
+    #[test]
+    fn xor() {
+        let a = example_block(1);
+        let b = example_block(2);
+        let res = a.xor(&b);
+
+        assert_eq!(res.words(), (a ^ b).words());
+        assert_eq!(a.xor(&a).words(), Block::zero().words());
+        assert_eq!(res.xor(&b).words(), a.words());
+    }
+
+    #[test]
+    fn to_le_bytes() {
+        let mut block = Block::zero();
+        block[0] = 0x0102_0304_0506_0708;
+        block[127] = 0x1112_1314_1516_1718;
+
+        let bytes = block.to_le_bytes();
+        assert_eq!(bytes[..8], [8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(
+            bytes[1016..],
+            [0x18, 0x17, 0x16, 0x15, 0x14, 0x13, 0x12, 0x11]
+        );
+
+        let mut loaded = Block::zero();
+        loaded.load(&bytes);
+        assert_eq!(loaded.words(), block.words());
+    }
+
+    /// Compare the SIMD implementation selected for the current CPU (if any)
+    /// with the portable one.
+    #[test]
+    fn compress_matches_soft() {
+        let compress = Block::compress_fn();
+
+        for seed in 1..16 {
+            let rhs = example_block(seed);
+            let lhs = example_block(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+
+            assert_eq!(compress(&rhs, &lhs).0, Block::compress_soft(&rhs, &lhs).0);
+        }
+    }
+
+    #[test]
+    fn compress() {
+        let rhs = example_block(1);
+        let lhs = example_block(2);
+
+        assert_eq!(
+            Block::compress(&rhs, &lhs).words(),
+            Block::compress_soft(&rhs, &lhs).words()
+        );
+    }
+}
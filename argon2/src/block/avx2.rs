@@ -0,0 +1,243 @@
+//! AVX2 implementation of the compression function.
+//!
+//! Adapted from the reference implementation's `blamka-round-opt.h`:
+//! <https://github.com/P-H-C/phc-winner-argon2/blob/master/src/blake2/blamka-round-opt.h>
+
+#![allow(unsafe_code)]
+
+use super::Block;
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+/// Number of 256-bit vectors in a block.
+const VECS_IN_BLOCK: usize = Block::SIZE / 32;
+
+/// Compression function `G` using 256-bit vectors.
+///
+/// # Safety
+///
+/// The CPU must support AVX2.
+#[target_feature(enable = "avx2")]
+pub(super) unsafe fn compress(rhs: &Block, lhs: &Block) -> Block {
+    let block_r = *rhs ^ *lhs;
+    let r_ptr = block_r.0.as_ptr() as *const __m256i;
+
+    let mut state = [_mm256_setzero_si256(); VECS_IN_BLOCK];
+    for (i, v) in state.iter_mut().enumerate() {
+        *v = _mm256_loadu_si256(r_ptr.add(i));
+    }
+
+    // Apply BLAKE2b rounds to two groups of 16 words at a time: first to
+    // the "columns" of the block, then to the "rows"
+    for i in 0..4 {
+        blake2_round(
+            &mut state,
+            [
+                8 * i,
+                8 * i + 4,
+                8 * i + 1,
+                8 * i + 5,
+                8 * i + 2,
+                8 * i + 6,
+                8 * i + 3,
+                8 * i + 7,
+            ],
+            false,
+        );
+    }
+
+    for i in 0..4 {
+        blake2_round(
+            &mut state,
+            [i, 4 + i, 8 + i, 12 + i, 16 + i, 20 + i, 24 + i, 28 + i],
+            true,
+        );
+    }
+
+    let mut block_q = Block::default();
+    let q_ptr = block_q.0.as_mut_ptr() as *mut __m256i;
+
+    for (i, v) in state.iter().enumerate() {
+        let r = _mm256_loadu_si256(r_ptr.add(i));
+        _mm256_storeu_si256(q_ptr.add(i), _mm256_xor_si256(*v, r));
+    }
+
+    block_q
+}
+
+/// Vectors a BLAKE2b round operates on, in the order
+/// `[A0, A1, B0, B1, C0, C1, D0, D1]`.
+type Vectors = [__m256i; 8];
+
+/// Apply a BLAKE2b round to the vectors of `state` at the given indices.
+///
+/// `rows` selects whether the vectors hold pairs of words from two rows of
+/// the block rather than four consecutive words.
+#[inline(always)]
+unsafe fn blake2_round(state: &mut [__m256i; VECS_IN_BLOCK], indices: [usize; 8], rows: bool) {
+    // Not `indices.map(..)`: it isn't inlined here, so the vectors would be
+    // passed through a function compiled without the target feature
+    let mut v = [
+        state[indices[0]],
+        state[indices[1]],
+        state[indices[2]],
+        state[indices[3]],
+        state[indices[4]],
+        state[indices[5]],
+        state[indices[6]],
+        state[indices[7]],
+    ];
+
+    g1(&mut v);
+    g2(&mut v);
+
+    if rows {
+        diagonalize_2(&mut v);
+    } else {
+        diagonalize_1(&mut v);
+    }
+
+    g1(&mut v);
+    g2(&mut v);
+
+    if rows {
+        undiagonalize_2(&mut v);
+    } else {
+        undiagonalize_1(&mut v);
+    }
+
+    for (i, x) in indices.iter().zip(v) {
+        state[*i] = x;
+    }
+}
+
+/// `x + y + 2 * lo(x) * lo(y)`, where `lo` is the low 32 bits of each word.
+#[inline(always)]
+unsafe fn blamka(x: __m256i, y: __m256i) -> __m256i {
+    let xy = _mm256_mul_epu32(x, y);
+    _mm256_add_epi64(_mm256_add_epi64(x, y), _mm256_add_epi64(xy, xy))
+}
+
+#[inline(always)]
+unsafe fn rotr32(x: __m256i) -> __m256i {
+    _mm256_shuffle_epi32(x, 0b10_11_00_01)
+}
+
+#[inline(always)]
+unsafe fn rotr24(x: __m256i) -> __m256i {
+    let mask = _mm256_setr_epi8(
+        3, 4, 5, 6, 7, 0, 1, 2, 11, 12, 13, 14, 15, 8, 9, 10, 3, 4, 5, 6, 7, 0, 1, 2, 11, 12, 13,
+        14, 15, 8, 9, 10,
+    );
+    _mm256_shuffle_epi8(x, mask)
+}
+
+#[inline(always)]
+unsafe fn rotr16(x: __m256i) -> __m256i {
+    let mask = _mm256_setr_epi8(
+        2, 3, 4, 5, 6, 7, 0, 1, 10, 11, 12, 13, 14, 15, 8, 9, 2, 3, 4, 5, 6, 7, 0, 1, 10, 11, 12,
+        13, 14, 15, 8, 9,
+    );
+    _mm256_shuffle_epi8(x, mask)
+}
+
+#[inline(always)]
+unsafe fn rotr63(x: __m256i) -> __m256i {
+    _mm256_xor_si256(_mm256_srli_epi64(x, 63), _mm256_add_epi64(x, x))
+}
+
+/// First half of the BLAKE2b `G` function.
+#[inline(always)]
+unsafe fn g1(v: &mut Vectors) {
+    let [a0, a1, b0, b1, c0, c1, d0, d1] = v;
+
+    *a0 = blamka(*a0, *b0);
+    *a1 = blamka(*a1, *b1);
+    *d0 = rotr32(_mm256_xor_si256(*d0, *a0));
+    *d1 = rotr32(_mm256_xor_si256(*d1, *a1));
+    *c0 = blamka(*c0, *d0);
+    *c1 = blamka(*c1, *d1);
+    *b0 = rotr24(_mm256_xor_si256(*b0, *c0));
+    *b1 = rotr24(_mm256_xor_si256(*b1, *c1));
+}
+
+/// Second half of the BLAKE2b `G` function.
+#[inline(always)]
+unsafe fn g2(v: &mut Vectors) {
+    let [a0, a1, b0, b1, c0, c1, d0, d1] = v;
+
+    *a0 = blamka(*a0, *b0);
+    *a1 = blamka(*a1, *b1);
+    *d0 = rotr16(_mm256_xor_si256(*d0, *a0));
+    *d1 = rotr16(_mm256_xor_si256(*d1, *a1));
+    *c0 = blamka(*c0, *d0);
+    *c1 = blamka(*c1, *d1);
+    *b0 = rotr63(_mm256_xor_si256(*b0, *c0));
+    *b1 = rotr63(_mm256_xor_si256(*b1, *c1));
+}
+
+/// Diagonalize when each vector holds four consecutive words of one row.
+#[inline(always)]
+unsafe fn diagonalize_1(v: &mut Vectors) {
+    let [_, _, b0, b1, c0, c1, d0, d1] = v;
+
+    *b0 = _mm256_permute4x64_epi64(*b0, 0b00_11_10_01);
+    *c0 = _mm256_permute4x64_epi64(*c0, 0b01_00_11_10);
+    *d0 = _mm256_permute4x64_epi64(*d0, 0b10_01_00_11);
+    *b1 = _mm256_permute4x64_epi64(*b1, 0b00_11_10_01);
+    *c1 = _mm256_permute4x64_epi64(*c1, 0b01_00_11_10);
+    *d1 = _mm256_permute4x64_epi64(*d1, 0b10_01_00_11);
+}
+
+/// Inverse of [`diagonalize_1`].
+#[inline(always)]
+unsafe fn undiagonalize_1(v: &mut Vectors) {
+    let [_, _, b0, b1, c0, c1, d0, d1] = v;
+
+    *b0 = _mm256_permute4x64_epi64(*b0, 0b10_01_00_11);
+    *c0 = _mm256_permute4x64_epi64(*c0, 0b01_00_11_10);
+    *d0 = _mm256_permute4x64_epi64(*d0, 0b00_11_10_01);
+    *b1 = _mm256_permute4x64_epi64(*b1, 0b10_01_00_11);
+    *c1 = _mm256_permute4x64_epi64(*c1, 0b01_00_11_10);
+    *d1 = _mm256_permute4x64_epi64(*d1, 0b00_11_10_01);
+}
+
+/// Diagonalize when each vector holds pairs of words from two rows.
+#[inline(always)]
+unsafe fn diagonalize_2(v: &mut Vectors) {
+    let [_, _, b0, b1, c0, c1, d0, d1] = v;
+
+    let tmp1 = _mm256_blend_epi32(*b0, *b1, 0b11_00_11_00);
+    let tmp2 = _mm256_blend_epi32(*b0, *b1, 0b00_11_00_11);
+    *b1 = _mm256_permute4x64_epi64(tmp1, 0b10_11_00_01);
+    *b0 = _mm256_permute4x64_epi64(tmp2, 0b10_11_00_01);
+
+    core::mem::swap(c0, c1);
+
+    let tmp1 = _mm256_blend_epi32(*d0, *d1, 0b11_00_11_00);
+    let tmp2 = _mm256_blend_epi32(*d0, *d1, 0b00_11_00_11);
+    *d0 = _mm256_permute4x64_epi64(tmp1, 0b10_11_00_01);
+    *d1 = _mm256_permute4x64_epi64(tmp2, 0b10_11_00_01);
+}
+
+/// Inverse of [`diagonalize_2`].
+#[inline(always)]
+unsafe fn undiagonalize_2(v: &mut Vectors) {
+    let [_, _, b0, b1, c0, c1, d0, d1] = v;
+
+    let tmp1 = _mm256_blend_epi32(*b0, *b1, 0b11_00_11_00);
+    let tmp2 = _mm256_blend_epi32(*b0, *b1, 0b00_11_00_11);
+    *b0 = _mm256_permute4x64_epi64(tmp1, 0b10_11_00_01);
+    *b1 = _mm256_permute4x64_epi64(tmp2, 0b10_11_00_01);
+
+    core::mem::swap(c0, c1);
+
+    let tmp1 = _mm256_blend_epi32(*d0, *d1, 0b00_11_00_11);
+    let tmp2 = _mm256_blend_epi32(*d0, *d1, 0b11_00_11_00);
+    *d0 = _mm256_permute4x64_epi64(tmp1, 0b10_11_00_01);
+    *d1 = _mm256_permute4x64_epi64(tmp2, 0b10_11_00_01);
+}
@@ -0,0 +1,177 @@
+//! NEON implementation of the compression function.
+//!
+//! Uses the same 128-bit vector layout as the SSE2 implementation.
+
+#![allow(unsafe_code)]
+
+use super::Block;
+use core::arch::aarch64::*;
+
+/// Number of 128-bit vectors in a block.
+const VECS_IN_BLOCK: usize = Block::SIZE / 16;
+
+/// Compression function `G` using 128-bit vectors.
+pub(super) fn compress(rhs: &Block, lhs: &Block) -> Block {
+    // SAFETY: this module is only compiled when NEON is enabled for the target
+    unsafe { compress_neon(rhs, lhs) }
+}
+
+#[target_feature(enable = "neon")]
+unsafe fn compress_neon(rhs: &Block, lhs: &Block) -> Block {
+    let block_r = *rhs ^ *lhs;
+    let r_ptr = block_r.0.as_ptr();
+
+    let mut state = [vdupq_n_u64(0); VECS_IN_BLOCK];
+    for (i, v) in state.iter_mut().enumerate() {
+        *v = vld1q_u64(r_ptr.add(2 * i));
+    }
+
+    // Apply BLAKE2b rounds to the "columns" of the block (16 consecutive
+    // words), then to the "rows" (pairs of words 16 words apart)
+    for i in 0..8 {
+        blake2_round(
+            &mut state,
+            [
+                8 * i,
+                8 * i + 1,
+                8 * i + 2,
+                8 * i + 3,
+                8 * i + 4,
+                8 * i + 5,
+                8 * i + 6,
+                8 * i + 7,
+            ],
+        );
+    }
+
+    for i in 0..8 {
+        blake2_round(
+            &mut state,
+            [i, i + 8, i + 16, i + 24, i + 32, i + 40, i + 48, i + 56],
+        );
+    }
+
+    let mut block_q = Block::default();
+    let q_ptr = block_q.0.as_mut_ptr();
+
+    for (i, v) in state.iter().enumerate() {
+        let r = vld1q_u64(r_ptr.add(2 * i));
+        vst1q_u64(q_ptr.add(2 * i), veorq_u64(*v, r));
+    }
+
+    block_q
+}
+
+/// Vectors a BLAKE2b round operates on, in the order
+/// `[A0, A1, B0, B1, C0, C1, D0, D1]`.
+type Vectors = [uint64x2_t; 8];
+
+/// Apply a BLAKE2b round to the vectors of `state` at the given indices.
+#[inline(always)]
+unsafe fn blake2_round(state: &mut [uint64x2_t; VECS_IN_BLOCK], indices: [usize; 8]) {
+    let mut v = indices.map(|i| state[i]);
+
+    g1(&mut v);
+    g2(&mut v);
+    diagonalize(&mut v);
+    g1(&mut v);
+    g2(&mut v);
+    undiagonalize(&mut v);
+
+    for (i, x) in indices.iter().zip(v) {
+        state[*i] = x;
+    }
+}
+
+/// `x + y + 2 * lo(x) * lo(y)`, where `lo` is the low 32 bits of each word.
+#[inline(always)]
+unsafe fn blamka(x: uint64x2_t, y: uint64x2_t) -> uint64x2_t {
+    let xy = vmull_u32(vmovn_u64(x), vmovn_u64(y));
+    vaddq_u64(vaddq_u64(x, y), vaddq_u64(xy, xy))
+}
+
+#[inline(always)]
+unsafe fn rotr32(x: uint64x2_t) -> uint64x2_t {
+    vreinterpretq_u64_u32(vrev64q_u32(vreinterpretq_u32_u64(x)))
+}
+
+#[inline(always)]
+unsafe fn rotr24(x: uint64x2_t) -> uint64x2_t {
+    vorrq_u64(vshrq_n_u64(x, 24), vshlq_n_u64(x, 40))
+}
+
+#[inline(always)]
+unsafe fn rotr16(x: uint64x2_t) -> uint64x2_t {
+    vorrq_u64(vshrq_n_u64(x, 16), vshlq_n_u64(x, 48))
+}
+
+#[inline(always)]
+unsafe fn rotr63(x: uint64x2_t) -> uint64x2_t {
+    veorq_u64(vshrq_n_u64(x, 63), vaddq_u64(x, x))
+}
+
+/// High word of `x` followed by the low word of `y`.
+#[inline(always)]
+unsafe fn hi_lo(x: uint64x2_t, y: uint64x2_t) -> uint64x2_t {
+    vextq_u64(x, y, 1)
+}
+
+/// First half of the BLAKE2b `G` function.
+#[inline(always)]
+unsafe fn g1(v: &mut Vectors) {
+    let [a0, a1, b0, b1, c0, c1, d0, d1] = v;
+
+    *a0 = blamka(*a0, *b0);
+    *a1 = blamka(*a1, *b1);
+    *d0 = rotr32(veorq_u64(*d0, *a0));
+    *d1 = rotr32(veorq_u64(*d1, *a1));
+    *c0 = blamka(*c0, *d0);
+    *c1 = blamka(*c1, *d1);
+    *b0 = rotr24(veorq_u64(*b0, *c0));
+    *b1 = rotr24(veorq_u64(*b1, *c1));
+}
+
+/// Second half of the BLAKE2b `G` function.
+#[inline(always)]
+unsafe fn g2(v: &mut Vectors) {
+    let [a0, a1, b0, b1, c0, c1, d0, d1] = v;
+
+    *a0 = blamka(*a0, *b0);
+    *a1 = blamka(*a1, *b1);
+    *d0 = rotr16(veorq_u64(*d0, *a0));
+    *d1 = rotr16(veorq_u64(*d1, *a1));
+    *c0 = blamka(*c0, *d0);
+    *c1 = blamka(*c1, *d1);
+    *b0 = rotr63(veorq_u64(*b0, *c0));
+    *b1 = rotr63(veorq_u64(*b1, *c1));
+}
+
+/// Rotate the `B`, `C` and `D` rows so the diagonals line up as columns.
+#[inline(always)]
+unsafe fn diagonalize(v: &mut Vectors) {
+    let [_, _, b0, b1, c0, c1, d0, d1] = v;
+
+    let (b0_new, b1_new) = (hi_lo(*b0, *b1), hi_lo(*b1, *b0));
+    let (d0_new, d1_new) = (hi_lo(*d1, *d0), hi_lo(*d0, *d1));
+
+    *b0 = b0_new;
+    *b1 = b1_new;
+    core::mem::swap(c0, c1);
+    *d0 = d0_new;
+    *d1 = d1_new;
+}
+
+/// Inverse of [`diagonalize`].
+#[inline(always)]
+unsafe fn undiagonalize(v: &mut Vectors) {
+    let [_, _, b0, b1, c0, c1, d0, d1] = v;
+
+    let (b0_new, b1_new) = (hi_lo(*b1, *b0), hi_lo(*b0, *b1));
+    let (d0_new, d1_new) = (hi_lo(*d0, *d1), hi_lo(*d1, *d0));
+
+    *b0 = b0_new;
+    *b1 = b1_new;
+    core::mem::swap(c0, c1);
+    *d0 = d0_new;
+    *d1 = d1_new;
+}
@@ -0,0 +1,180 @@
+//! WebAssembly SIMD128 implementation of the compression function.
+//!
+//! Uses the same 128-bit vector layout as the SSE2 implementation.
+
+#![allow(unsafe_code)]
+
+use super::Block;
+use core::arch::wasm32::*;
+
+/// Number of 128-bit vectors in a block.
+const VECS_IN_BLOCK: usize = Block::SIZE / 16;
+
+/// Compression function `G` using 128-bit vectors.
+pub(super) fn compress(rhs: &Block, lhs: &Block) -> Block {
+    // SAFETY: this module is only compiled when SIMD128 is enabled for the target
+    unsafe { compress_simd128(rhs, lhs) }
+}
+
+#[target_feature(enable = "simd128")]
+unsafe fn compress_simd128(rhs: &Block, lhs: &Block) -> Block {
+    let block_r = *rhs ^ *lhs;
+    let r_ptr = block_r.0.as_ptr() as *const v128;
+
+    let mut state = [u64x2_splat(0); VECS_IN_BLOCK];
+    for (i, v) in state.iter_mut().enumerate() {
+        *v = v128_load(r_ptr.add(i));
+    }
+
+    // Apply BLAKE2b rounds to the "columns" of the block (16 consecutive
+    // words), then to the "rows" (pairs of words 16 words apart)
+    for i in 0..8 {
+        blake2_round(
+            &mut state,
+            [
+                8 * i,
+                8 * i + 1,
+                8 * i + 2,
+                8 * i + 3,
+                8 * i + 4,
+                8 * i + 5,
+                8 * i + 6,
+                8 * i + 7,
+            ],
+        );
+    }
+
+    for i in 0..8 {
+        blake2_round(
+            &mut state,
+            [i, i + 8, i + 16, i + 24, i + 32, i + 40, i + 48, i + 56],
+        );
+    }
+
+    let mut block_q = Block::default();
+    let q_ptr = block_q.0.as_mut_ptr() as *mut v128;
+
+    for (i, v) in state.iter().enumerate() {
+        let r = v128_load(r_ptr.add(i));
+        v128_store(q_ptr.add(i), v128_xor(*v, r));
+    }
+
+    block_q
+}
+
+/// Vectors a BLAKE2b round operates on, in the order
+/// `[A0, A1, B0, B1, C0, C1, D0, D1]`.
+type Vectors = [v128; 8];
+
+/// Apply a BLAKE2b round to the vectors of `state` at the given indices.
+#[inline(always)]
+unsafe fn blake2_round(state: &mut [v128; VECS_IN_BLOCK], indices: [usize; 8]) {
+    let mut v = indices.map(|i| state[i]);
+
+    g1(&mut v);
+    g2(&mut v);
+    diagonalize(&mut v);
+    g1(&mut v);
+    g2(&mut v);
+    undiagonalize(&mut v);
+
+    for (i, x) in indices.iter().zip(v) {
+        state[*i] = x;
+    }
+}
+
+/// `x + y + 2 * lo(x) * lo(y)`, where `lo` is the low 32 bits of each word.
+#[inline(always)]
+unsafe fn blamka(x: v128, y: v128) -> v128 {
+    // Gather the low 32 bits of each word into the low half of the vector
+    let x_lo = u32x4_shuffle::<0, 2, 0, 2>(x, x);
+    let y_lo = u32x4_shuffle::<0, 2, 0, 2>(y, y);
+    let xy = u64x2_extmul_low_u32x4(x_lo, y_lo);
+    i64x2_add(i64x2_add(x, y), i64x2_add(xy, xy))
+}
+
+#[inline(always)]
+unsafe fn rotr32(x: v128) -> v128 {
+    u32x4_shuffle::<1, 0, 3, 2>(x, x)
+}
+
+#[inline(always)]
+unsafe fn rotr24(x: v128) -> v128 {
+    u8x16_shuffle::<3, 4, 5, 6, 7, 0, 1, 2, 11, 12, 13, 14, 15, 8, 9, 10>(x, x)
+}
+
+#[inline(always)]
+unsafe fn rotr16(x: v128) -> v128 {
+    u8x16_shuffle::<2, 3, 4, 5, 6, 7, 0, 1, 10, 11, 12, 13, 14, 15, 8, 9>(x, x)
+}
+
+#[inline(always)]
+unsafe fn rotr63(x: v128) -> v128 {
+    v128_xor(u64x2_shr(x, 63), i64x2_add(x, x))
+}
+
+/// High word of `x` followed by the low word of `y`.
+#[inline(always)]
+unsafe fn hi_lo(x: v128, y: v128) -> v128 {
+    u64x2_shuffle::<1, 2>(x, y)
+}
+
+/// First half of the BLAKE2b `G` function.
+#[inline(always)]
+unsafe fn g1(v: &mut Vectors) {
+    let [a0, a1, b0, b1, c0, c1, d0, d1] = v;
+
+    *a0 = blamka(*a0, *b0);
+    *a1 = blamka(*a1, *b1);
+    *d0 = rotr32(v128_xor(*d0, *a0));
+    *d1 = rotr32(v128_xor(*d1, *a1));
+    *c0 = blamka(*c0, *d0);
+    *c1 = blamka(*c1, *d1);
+    *b0 = rotr24(v128_xor(*b0, *c0));
+    *b1 = rotr24(v128_xor(*b1, *c1));
+}
+
+/// Second half of the BLAKE2b `G` function.
+#[inline(always)]
+unsafe fn g2(v: &mut Vectors) {
+    let [a0, a1, b0, b1, c0, c1, d0, d1] = v;
+
+    *a0 = blamka(*a0, *b0);
+    *a1 = blamka(*a1, *b1);
+    *d0 = rotr16(v128_xor(*d0, *a0));
+    *d1 = rotr16(v128_xor(*d1, *a1));
+    *c0 = blamka(*c0, *d0);
+    *c1 = blamka(*c1, *d1);
+    *b0 = rotr63(v128_xor(*b0, *c0));
+    *b1 = rotr63(v128_xor(*b1, *c1));
+}
+
+/// Rotate the `B`, `C` and `D` rows so the diagonals line up as columns.
+#[inline(always)]
+unsafe fn diagonalize(v: &mut Vectors) {
+    let [_, _, b0, b1, c0, c1, d0, d1] = v;
+
+    let (b0_new, b1_new) = (hi_lo(*b0, *b1), hi_lo(*b1, *b0));
+    let (d0_new, d1_new) = (hi_lo(*d1, *d0), hi_lo(*d0, *d1));
+
+    *b0 = b0_new;
+    *b1 = b1_new;
+    core::mem::swap(c0, c1);
+    *d0 = d0_new;
+    *d1 = d1_new;
+}
+
+/// Inverse of [`diagonalize`].
+#[inline(always)]
+unsafe fn undiagonalize(v: &mut Vectors) {
+    let [_, _, b0, b1, c0, c1, d0, d1] = v;
+
+    let (b0_new, b1_new) = (hi_lo(*b1, *b0), hi_lo(*b0, *b1));
+    let (d0_new, d1_new) = (hi_lo(*d0, *d1), hi_lo(*d1, *d0));
+
+    *b0 = b0_new;
+    *b1 = b1_new;
+    core::mem::swap(c0, c1);
+    *d0 = d0_new;
+    *d1 = d1_new;
+}
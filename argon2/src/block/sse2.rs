@@ -0,0 +1,197 @@
+//! SSE2 implementation of the compression function.
+//!
+//! Adapted from the reference implementation's `blamka-round-opt.h`:
+//! <https://github.com/P-H-C/phc-winner-argon2/blob/master/src/blake2/blamka-round-opt.h>
+
+#![allow(unsafe_code)]
+
+use super::Block;
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+/// Number of 128-bit vectors in a block.
+const VECS_IN_BLOCK: usize = Block::SIZE / 16;
+
+/// Compression function `G` using 128-bit vectors.
+///
+/// # Safety
+///
+/// The CPU must support SSE2.
+#[target_feature(enable = "sse2")]
+pub(super) unsafe fn compress(rhs: &Block, lhs: &Block) -> Block {
+    let block_r = *rhs ^ *lhs;
+    let r_ptr = block_r.0.as_ptr() as *const __m128i;
+
+    let mut state = [_mm_setzero_si128(); VECS_IN_BLOCK];
+    for (i, v) in state.iter_mut().enumerate() {
+        *v = _mm_loadu_si128(r_ptr.add(i));
+    }
+
+    // Apply BLAKE2b rounds to the "columns" of the block (16 consecutive
+    // words), then to the "rows" (pairs of words 16 words apart)
+    for i in 0..8 {
+        blake2_round(
+            &mut state,
+            [
+                8 * i,
+                8 * i + 1,
+                8 * i + 2,
+                8 * i + 3,
+                8 * i + 4,
+                8 * i + 5,
+                8 * i + 6,
+                8 * i + 7,
+            ],
+        );
+    }
+
+    for i in 0..8 {
+        blake2_round(
+            &mut state,
+            [i, i + 8, i + 16, i + 24, i + 32, i + 40, i + 48, i + 56],
+        );
+    }
+
+    let mut block_q = Block::default();
+    let q_ptr = block_q.0.as_mut_ptr() as *mut __m128i;
+
+    for (i, v) in state.iter().enumerate() {
+        let r = _mm_loadu_si128(r_ptr.add(i));
+        _mm_storeu_si128(q_ptr.add(i), _mm_xor_si128(*v, r));
+    }
+
+    block_q
+}
+
+/// Vectors a BLAKE2b round operates on, in the order
+/// `[A0, A1, B0, B1, C0, C1, D0, D1]`.
+type Vectors = [__m128i; 8];
+
+/// Apply a BLAKE2b round to the vectors of `state` at the given indices.
+#[inline(always)]
+unsafe fn blake2_round(state: &mut [__m128i; VECS_IN_BLOCK], indices: [usize; 8]) {
+    // Not `indices.map(..)`: it isn't inlined here, so the vectors would be
+    // passed through a function compiled without the target feature
+    let mut v = [
+        state[indices[0]],
+        state[indices[1]],
+        state[indices[2]],
+        state[indices[3]],
+        state[indices[4]],
+        state[indices[5]],
+        state[indices[6]],
+        state[indices[7]],
+    ];
+
+    g1(&mut v);
+    g2(&mut v);
+    diagonalize(&mut v);
+    g1(&mut v);
+    g2(&mut v);
+    undiagonalize(&mut v);
+
+    for (i, x) in indices.iter().zip(v) {
+        state[*i] = x;
+    }
+}
+
+/// `x + y + 2 * lo(x) * lo(y)`, where `lo` is the low 32 bits of each word.
+#[inline(always)]
+unsafe fn blamka(x: __m128i, y: __m128i) -> __m128i {
+    let xy = _mm_mul_epu32(x, y);
+    _mm_add_epi64(_mm_add_epi64(x, y), _mm_add_epi64(xy, xy))
+}
+
+#[inline(always)]
+unsafe fn rotr32(x: __m128i) -> __m128i {
+    _mm_shuffle_epi32(x, 0b10_11_00_01)
+}
+
+#[inline(always)]
+unsafe fn rotr24(x: __m128i) -> __m128i {
+    _mm_or_si128(_mm_srli_epi64(x, 24), _mm_slli_epi64(x, 40))
+}
+
+#[inline(always)]
+unsafe fn rotr16(x: __m128i) -> __m128i {
+    _mm_or_si128(_mm_srli_epi64(x, 16), _mm_slli_epi64(x, 48))
+}
+
+#[inline(always)]
+unsafe fn rotr63(x: __m128i) -> __m128i {
+    _mm_xor_si128(_mm_srli_epi64(x, 63), _mm_add_epi64(x, x))
+}
+
+/// High word of `x` followed by the low word of `y`.
+#[inline(always)]
+unsafe fn hi_lo(x: __m128i, y: __m128i) -> __m128i {
+    _mm_castpd_si128(_mm_shuffle_pd(
+        _mm_castsi128_pd(x),
+        _mm_castsi128_pd(y),
+        0b01,
+    ))
+}
+
+/// First half of the BLAKE2b `G` function.
+#[inline(always)]
+unsafe fn g1(v: &mut Vectors) {
+    let [a0, a1, b0, b1, c0, c1, d0, d1] = v;
+
+    *a0 = blamka(*a0, *b0);
+    *a1 = blamka(*a1, *b1);
+    *d0 = rotr32(_mm_xor_si128(*d0, *a0));
+    *d1 = rotr32(_mm_xor_si128(*d1, *a1));
+    *c0 = blamka(*c0, *d0);
+    *c1 = blamka(*c1, *d1);
+    *b0 = rotr24(_mm_xor_si128(*b0, *c0));
+    *b1 = rotr24(_mm_xor_si128(*b1, *c1));
+}
+
+/// Second half of the BLAKE2b `G` function.
+#[inline(always)]
+unsafe fn g2(v: &mut Vectors) {
+    let [a0, a1, b0, b1, c0, c1, d0, d1] = v;
+
+    *a0 = blamka(*a0, *b0);
+    *a1 = blamka(*a1, *b1);
+    *d0 = rotr16(_mm_xor_si128(*d0, *a0));
+    *d1 = rotr16(_mm_xor_si128(*d1, *a1));
+    *c0 = blamka(*c0, *d0);
+    *c1 = blamka(*c1, *d1);
+    *b0 = rotr63(_mm_xor_si128(*b0, *c0));
+    *b1 = rotr63(_mm_xor_si128(*b1, *c1));
+}
+
+/// Rotate the `B`, `C` and `D` rows so the diagonals line up as columns.
+#[inline(always)]
+unsafe fn diagonalize(v: &mut Vectors) {
+    let [_, _, b0, b1, c0, c1, d0, d1] = v;
+
+    let (b0_new, b1_new) = (hi_lo(*b0, *b1), hi_lo(*b1, *b0));
+    let (d0_new, d1_new) = (hi_lo(*d1, *d0), hi_lo(*d0, *d1));
+
+    *b0 = b0_new;
+    *b1 = b1_new;
+    core::mem::swap(c0, c1);
+    *d0 = d0_new;
+    *d1 = d1_new;
+}
+
+/// Inverse of [`diagonalize`].
+#[inline(always)]
+unsafe fn undiagonalize(v: &mut Vectors) {
+    let [_, _, b0, b1, c0, c1, d0, d1] = v;
+
+    let (b0_new, b1_new) = (hi_lo(*b1, *b0), hi_lo(*b0, *b1));
+    let (d0_new, d1_new) = (hi_lo(*d0, *d1), hi_lo(*d1, *d0));
+
+    *b0 = b0_new;
+    *b1 = b1_new;
+    core::mem::swap(c0, c1);
+    *d0 = d0_new;
+    *d1 = d1_new;
+}
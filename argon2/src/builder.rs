@@ -0,0 +1,131 @@
+//! Builder for the Argon2 context.
+
+use crate::{Algorithm, Argon2, Params, Result, Version};
+
+/// Builder for an [`Argon2`] context.
+///
+/// Every field is optional and defaults to the same value as
+/// [`Argon2::default`]. The secret and thread limit are validated when the
+/// context is built with [`Argon2Builder::build`].
+#[derive(Clone, Default)]
+pub struct Argon2Builder<'key> {
+    /// Algorithm to use
+    algorithm: Algorithm,
+
+    /// Version number
+    version: Version,
+
+    /// Algorithm parameters
+    params: Params,
+
+    /// Key array
+    secret: Option<&'key [u8]>,
+
+    /// Maximum number of threads
+    max_threads: Option<usize>,
+}
+
+impl<'key> Argon2Builder<'key> {
+    /// Create a new builder with the default algorithm, version and
+    /// parameters, and no secret.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the algorithm.
+    pub fn algorithm(&mut self, algorithm: Algorithm) -> &mut Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Set the version.
+    pub fn version(&mut self, version: Version) -> &mut Self {
+        self.version = version;
+        self
+    }
+
+    /// Set the parameters.
+    pub fn params(&mut self, params: Params) -> &mut Self {
+        self.params = params;
+        self
+    }
+
+    /// Set the secret key a.k.a. "pepper", at most [`crate::MAX_SECRET_LEN`]
+    /// bytes long.
+    pub fn secret(&mut self, secret: &'key [u8]) -> &mut Self {
+        self.secret = Some(secret);
+        self
+    }
+
+    /// Set the maximum number of threads, see [`Argon2::with_max_threads`].
+    pub fn max_threads(&mut self, max_threads: usize) -> &mut Self {
+        self.max_threads = Some(max_threads);
+        self
+    }
+
+    /// Build the [`Argon2`] context.
+    ///
+    /// Returns [`crate::Error::SecretTooLong`] if the secret is too long,
+    /// and [`crate::Error::ThreadsTooFew`] if the maximum number of threads
+    /// is zero.
+    pub fn build(&self) -> Result<Argon2<'key>> {
+        let argon2 = match self.secret {
+            Some(secret) => {
+                Argon2::new_with_secret(secret, self.algorithm, self.version, self.params.clone())?
+            }
+            None => Argon2::new(self.algorithm, self.version, self.params.clone()),
+        };
+
+        match self.max_threads {
+            Some(max_threads) => argon2.with_max_threads(max_threads),
+            None => Ok(argon2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Argon2Builder;
+    use crate::{Algorithm, Argon2, Error, Params, Version};
+
+    #[test]
+    fn build() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let secret = b"pepper";
+
+        let argon2 = Argon2Builder::new()
+            .algorithm(Algorithm::Argon2d)
+            .version(Version::V0x10)
+            .params(params.clone())
+            .secret(secret)
+            .max_threads(1)
+            .build()
+            .unwrap();
+
+        assert_eq!(argon2.algorithm, Algorithm::Argon2d);
+        assert_eq!(argon2.version, Version::V0x10);
+        assert_eq!(argon2.params, params);
+        assert_eq!(argon2.secret, Some(&secret[..]));
+        assert_eq!(argon2.max_threads, 1);
+    }
+
+    #[test]
+    fn build_default() {
+        let argon2 = Argon2Builder::new().build().unwrap();
+        let default = Argon2::default();
+
+        assert_eq!(argon2.algorithm, default.algorithm);
+        assert_eq!(argon2.version, default.version);
+        assert_eq!(argon2.params, default.params);
+        assert_eq!(argon2.secret, None);
+        assert_eq!(argon2.max_threads, default.max_threads);
+    }
+
+    #[test]
+    fn build_invalid() {
+        assert_eq!(
+            Argon2Builder::new().max_threads(0).build().err(),
+            Some(Error::ThreadsTooFew)
+        );
+    }
+}
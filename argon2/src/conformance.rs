@@ -0,0 +1,381 @@
+//! Conformance tests against the known answers of the reference
+//! implementation.
+//!
+//! This runs the RFC 9106 test vectors, which use a secret key and
+//! associated data, and the test vectors of the reference implementation at
+//! multiple costs:
+//! <https://github.com/P-H-C/phc-winner-argon2/blob/master/src/test.c>
+//!
+//! The vectors with memory costs of 256 MiB and more are only run by the
+//! `kat` integration tests.
+
+use crate::{self_test::VECTORS, Algorithm, Argon2, Block, Params, ParamsBuilder, Version};
+
+/// Test vector of the reference implementation, without secret key or
+/// associated data and with a 32 byte tag.
+struct Vector {
+    algorithm: Algorithm,
+    version: Version,
+    t_cost: u32,
+    m_cost: u32,
+    p_cost: u32,
+    password: &'static [u8],
+    salt: &'static [u8],
+    tag: [u8; 32],
+}
+
+/// Test vectors of the reference implementation.
+const REFERENCE_VECTORS: &[Vector] = &[
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x10,
+        t_cost: 2,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0xf6, 0xc4, 0xdb, 0x4a, 0x54, 0xe2, 0xa3, 0x70, 0x62, 0x7a, 0xff, 0x3d, 0xb6, 0x17,
+            0x6b, 0x94, 0xa2, 0xa2, 0x09, 0xa6, 0x2c, 0x8e, 0x36, 0x15, 0x27, 0x11, 0x80, 0x2f,
+            0x7b, 0x30, 0xc6, 0x94,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x10,
+        t_cost: 2,
+        m_cost: 1 << 8,
+        p_cost: 1,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0xfd, 0x4d, 0xd8, 0x3d, 0x76, 0x2c, 0x49, 0xbd, 0xea, 0xf5, 0x7c, 0x47, 0xbd, 0xcd,
+            0x0c, 0x2f, 0x1b, 0xab, 0xf8, 0x63, 0xfd, 0xeb, 0x49, 0x0d, 0xf6, 0x3e, 0xde, 0x99,
+            0x75, 0xfc, 0xcf, 0x06,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x10,
+        t_cost: 2,
+        m_cost: 1 << 8,
+        p_cost: 2,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0xb6, 0xc1, 0x15, 0x60, 0xa6, 0xa9, 0xd6, 0x1e, 0xac, 0x70, 0x6b, 0x79, 0xa2, 0xf9,
+            0x7d, 0x68, 0xb4, 0x46, 0x3a, 0xa3, 0xad, 0x87, 0xe0, 0x0c, 0x07, 0xe2, 0xb0, 0x1e,
+            0x90, 0xc5, 0x64, 0xfb,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x10,
+        t_cost: 1,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0x81, 0x63, 0x05, 0x52, 0xb8, 0xf3, 0xb1, 0xf4, 0x8c, 0xdb, 0x19, 0x92, 0xc4, 0xc6,
+            0x78, 0x64, 0x3d, 0x49, 0x0b, 0x2b, 0x5e, 0xb4, 0xff, 0x6c, 0x4b, 0x34, 0x38, 0xb5,
+            0x62, 0x17, 0x24, 0xb2,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x10,
+        t_cost: 4,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0xf2, 0x12, 0xf0, 0x16, 0x15, 0xe6, 0xeb, 0x5d, 0x74, 0x73, 0x4d, 0xc3, 0xef, 0x40,
+            0xad, 0xe2, 0xd5, 0x1d, 0x05, 0x24, 0x68, 0xd8, 0xc6, 0x94, 0x40, 0xa3, 0xa1, 0xf2,
+            0xc1, 0xc2, 0x84, 0x7b,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x10,
+        t_cost: 2,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"differentpassword",
+        salt: b"somesalt",
+        tag: [
+            0xe9, 0xc9, 0x02, 0x07, 0x4b, 0x67, 0x54, 0x53, 0x1a, 0x3a, 0x0b, 0xe5, 0x19, 0xe5,
+            0xba, 0xf4, 0x04, 0xb3, 0x0c, 0xe6, 0x9b, 0x3f, 0x01, 0xac, 0x3b, 0xf2, 0x12, 0x29,
+            0x96, 0x01, 0x09, 0xa3,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x10,
+        t_cost: 2,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"password",
+        salt: b"diffsalt",
+        tag: [
+            0x79, 0xa1, 0x03, 0xb9, 0x0f, 0xe8, 0xae, 0xf8, 0x57, 0x0c, 0xb3, 0x1f, 0xc8, 0xb2,
+            0x22, 0x59, 0x77, 0x89, 0x16, 0xf8, 0x33, 0x6b, 0x7b, 0xda, 0xc3, 0x89, 0x25, 0x69,
+            0xd4, 0xf1, 0xc4, 0x97,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x13,
+        t_cost: 2,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0xc1, 0x62, 0x88, 0x32, 0x14, 0x7d, 0x97, 0x20, 0xc5, 0xbd, 0x1c, 0xfd, 0x61, 0x36,
+            0x70, 0x78, 0x72, 0x9f, 0x6d, 0xfb, 0x6f, 0x8f, 0xea, 0x9f, 0xf9, 0x81, 0x58, 0xe0,
+            0xd7, 0x81, 0x6e, 0xd0,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x13,
+        t_cost: 2,
+        m_cost: 1 << 8,
+        p_cost: 1,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0x89, 0xe9, 0x02, 0x9f, 0x46, 0x37, 0xb2, 0x95, 0xbe, 0xb0, 0x27, 0x05, 0x6a, 0x73,
+            0x36, 0xc4, 0x14, 0xfa, 0xdd, 0x43, 0xf6, 0xb2, 0x08, 0x64, 0x52, 0x81, 0xcb, 0x21,
+            0x4a, 0x56, 0x45, 0x2f,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x13,
+        t_cost: 2,
+        m_cost: 1 << 8,
+        p_cost: 2,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0x4f, 0xf5, 0xce, 0x27, 0x69, 0xa1, 0xd7, 0xf4, 0xc8, 0xa4, 0x91, 0xdf, 0x09, 0xd4,
+            0x1a, 0x9f, 0xbe, 0x90, 0xe5, 0xeb, 0x02, 0x15, 0x5a, 0x13, 0xe4, 0xc0, 0x1e, 0x20,
+            0xcd, 0x4e, 0xab, 0x61,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x13,
+        t_cost: 1,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0xd1, 0x68, 0x07, 0x5c, 0x4d, 0x98, 0x5e, 0x13, 0xeb, 0xea, 0xe5, 0x60, 0xcf, 0x8b,
+            0x94, 0xc3, 0xb5, 0xd8, 0xa1, 0x6c, 0x51, 0x91, 0x6b, 0x6f, 0x4a, 0xc2, 0xda, 0x3a,
+            0xc1, 0x1b, 0xbe, 0xcf,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x13,
+        t_cost: 4,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0xaa, 0xa9, 0x53, 0xd5, 0x8a, 0xf3, 0x70, 0x6c, 0xe3, 0xdf, 0x1a, 0xef, 0xd4, 0xa6,
+            0x4a, 0x84, 0xe3, 0x1d, 0x7f, 0x54, 0x17, 0x52, 0x31, 0xf1, 0x28, 0x52, 0x59, 0xf8,
+            0x81, 0x74, 0xce, 0x5b,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x13,
+        t_cost: 2,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"differentpassword",
+        salt: b"somesalt",
+        tag: [
+            0x14, 0xae, 0x8d, 0xa0, 0x1a, 0xfe, 0xa8, 0x70, 0x0c, 0x23, 0x58, 0xdc, 0xef, 0x7c,
+            0x53, 0x58, 0xd9, 0x02, 0x12, 0x82, 0xbd, 0x88, 0x66, 0x3a, 0x45, 0x62, 0xf5, 0x9f,
+            0xb7, 0x4d, 0x22, 0xee,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2i,
+        version: Version::V0x13,
+        t_cost: 2,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"password",
+        salt: b"diffsalt",
+        tag: [
+            0xb0, 0x35, 0x7c, 0xcc, 0xfb, 0xef, 0x91, 0xf3, 0x86, 0x0b, 0x0d, 0xba, 0x44, 0x7b,
+            0x23, 0x48, 0xcb, 0xef, 0xec, 0xad, 0xaf, 0x99, 0x0a, 0xbf, 0xe9, 0xcc, 0x40, 0x72,
+            0x6c, 0x52, 0x12, 0x71,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2id,
+        version: Version::V0x13,
+        t_cost: 2,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0x09, 0x31, 0x61, 0x15, 0xd5, 0xcf, 0x24, 0xed, 0x5a, 0x15, 0xa3, 0x1a, 0x3b, 0xa3,
+            0x26, 0xe5, 0xcf, 0x32, 0xed, 0xc2, 0x47, 0x02, 0x98, 0x7c, 0x02, 0xb6, 0x56, 0x6f,
+            0x61, 0x91, 0x3c, 0xf7,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2id,
+        version: Version::V0x13,
+        t_cost: 2,
+        m_cost: 1 << 8,
+        p_cost: 1,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0x9d, 0xfe, 0xb9, 0x10, 0xe8, 0x0b, 0xad, 0x03, 0x11, 0xfe, 0xe2, 0x0f, 0x9c, 0x0e,
+            0x2b, 0x12, 0xc1, 0x79, 0x87, 0xb4, 0xca, 0xc9, 0x0c, 0x2e, 0xf5, 0x4d, 0x5b, 0x30,
+            0x21, 0xc6, 0x8b, 0xfe,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2id,
+        version: Version::V0x13,
+        t_cost: 2,
+        m_cost: 1 << 8,
+        p_cost: 2,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0x6d, 0x09, 0x3c, 0x50, 0x1f, 0xd5, 0x99, 0x96, 0x45, 0xe0, 0xea, 0x3b, 0xf6, 0x20,
+            0xd7, 0xb8, 0xbe, 0x7f, 0xd2, 0xdb, 0x59, 0xc2, 0x0d, 0x9f, 0xff, 0x95, 0x39, 0xda,
+            0x2b, 0xf5, 0x70, 0x37,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2id,
+        version: Version::V0x13,
+        t_cost: 1,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0xf6, 0xa5, 0xad, 0xc1, 0xba, 0x72, 0x3d, 0xdd, 0xef, 0x9b, 0x5a, 0xc1, 0xd4, 0x64,
+            0xe1, 0x80, 0xfc, 0xd9, 0xdf, 0xfc, 0x9d, 0x1c, 0xbf, 0x76, 0xcc, 0xa2, 0xfe, 0xd7,
+            0x95, 0xd9, 0xca, 0x98,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2id,
+        version: Version::V0x13,
+        t_cost: 4,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"password",
+        salt: b"somesalt",
+        tag: [
+            0x90, 0x25, 0xd4, 0x8e, 0x68, 0xef, 0x73, 0x95, 0xcc, 0xa9, 0x07, 0x9d, 0xa4, 0xc4,
+            0xec, 0x3a, 0xff, 0xb3, 0xc8, 0x91, 0x1f, 0xe4, 0xf8, 0x6d, 0x1a, 0x25, 0x20, 0x85,
+            0x6f, 0x63, 0x17, 0x2c,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2id,
+        version: Version::V0x13,
+        t_cost: 2,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"differentpassword",
+        salt: b"somesalt",
+        tag: [
+            0x0b, 0x84, 0xd6, 0x52, 0xcf, 0x6b, 0x0c, 0x4b, 0xea, 0xef, 0x0d, 0xfe, 0x27, 0x8b,
+            0xa6, 0xa8, 0x0d, 0xf6, 0x69, 0x62, 0x81, 0xd7, 0xe0, 0xd2, 0x89, 0x1b, 0x81, 0x7d,
+            0x8c, 0x45, 0x8f, 0xde,
+        ],
+    },
+    Vector {
+        algorithm: Algorithm::Argon2id,
+        version: Version::V0x13,
+        t_cost: 2,
+        m_cost: 1 << 16,
+        p_cost: 1,
+        password: b"password",
+        salt: b"diffsalt",
+        tag: [
+            0xbd, 0xf3, 0x2b, 0x05, 0xcc, 0xc4, 0x2e, 0xb1, 0x5d, 0x58, 0xfd, 0x19, 0xb1, 0xf8,
+            0x56, 0xb1, 0x13, 0xda, 0x1e, 0x9a, 0x58, 0x74, 0xfd, 0xcc, 0x54, 0x43, 0x08, 0x56,
+            0x5a, 0xa8, 0x14, 0x1c,
+        ],
+    },
+];
+
+/// Hash with both [`Argon2::hash_password_into`] and a sequential fill into
+/// caller-provided memory, which must give the same tag.
+fn hash(argon2: &Argon2<'_>, password: &[u8], salt: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    argon2.hash_password_into(password, salt, &mut out).unwrap();
+
+    let mut memory = vec![Block::default(); argon2.params().block_count()];
+    let mut sequential_out = [0u8; 32];
+    argon2
+        .clone()
+        .with_parallelism(false)
+        .hash_password_into_with_memory(password, salt, &mut sequential_out, &mut memory)
+        .unwrap();
+    assert_eq!(out, sequential_out);
+
+    out
+}
+
+#[test]
+fn rfc9106() {
+    let mut builder = ParamsBuilder::new();
+    builder.m_cost(32).unwrap();
+    builder.t_cost(3).unwrap();
+    builder.p_cost(4).unwrap();
+    builder.data(&[0x04; 12]).unwrap();
+    let params = builder.params().unwrap();
+
+    for (algorithm, version, tag) in VECTORS {
+        let argon2 =
+            Argon2::new_with_secret(&[0x03; 8], algorithm, version, params.clone()).unwrap();
+        assert_eq!(
+            hash(&argon2, &[0x01; 32], &[0x02; 16]),
+            tag,
+            "{:?} {:?}",
+            algorithm,
+            version
+        );
+    }
+}
+
+#[test]
+fn reference_implementation() {
+    for vector in REFERENCE_VECTORS {
+        let params = Params::new(vector.m_cost, vector.t_cost, vector.p_cost, None).unwrap();
+        let argon2 = Argon2::new(vector.algorithm, vector.version, params);
+        assert_eq!(
+            hash(&argon2, vector.password, vector.salt),
+            vector.tag,
+            "{:?} {:?} m={},t={},p={}",
+            vector.algorithm,
+            vector.version,
+            vector.m_cost,
+            vector.t_cost,
+            vector.p_cost
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Encoded hash format of the Argon2 reference implementation.
+//!
+//! This is the `$argon2id$v=19$m=65536,t=3,p=4$<salt>$<hash>` format produced
+//! by the reference C implementation (`argon2_encode`), where the salt and
+//! hash are encoded as standard Base64 without padding.
+//!
+//! It is similar to the PHC string format supported through the
+//! `password-hash` crate, but parsed independently of its stricter rules,
+//! e.g. on the length of the salt.
+
+use crate::{Algorithm, Argon2, Error, Params, ParamsBuilder, Result, Version};
+use alloc::{string::String, vec::Vec};
+use base64ct::{Base64Unpadded, Encoding};
+use core::fmt::Write;
+
+impl Argon2<'static> {
+    /// Parse a hash in the encoded format of the reference C implementation,
+    /// i.e. `$argon2id$v=19$m=65536,t=3,p=4$<salt>$<hash>`.
+    ///
+    /// Returns an Argon2 context with the encoded algorithm, version and
+    /// parameters, along with the decoded salt and hash. The output length
+    /// of the parameters is set to the length of the hash, so the password
+    /// can be verified by hashing it with the salt and comparing the result
+    /// with the hash.
+    ///
+    /// A missing version (`v=`) implies [`Version::V0x10`], as with the
+    /// reference implementation. Base64 padding is accepted but not
+    /// required.
+    pub fn from_encoded(s: &str) -> Result<(Self, Vec<u8>, Vec<u8>)> {
+        let mut fields = s
+            .strip_prefix('$')
+            .ok_or(Error::EncodingInvalid)?
+            .split('$');
+        let mut next_field = || fields.next().ok_or(Error::EncodingInvalid);
+
+        let algorithm = next_field()?.parse::<Algorithm>()?;
+
+        let mut field = next_field()?;
+        let version = match field.strip_prefix("v=") {
+            Some(version) => {
+                field = next_field()?;
+                Version::try_from(decode_decimal(version)?)?
+            }
+            None => Version::V0x10,
+        };
+
+        let mut costs = field.split(',');
+        let mut next_cost = |name| {
+            costs
+                .next()
+                .and_then(|cost: &str| cost.strip_prefix(name))
+                .ok_or(Error::EncodingInvalid)
+                .and_then(decode_decimal)
+        };
+
+        let mut builder = ParamsBuilder::new();
+        builder.m_cost(next_cost("m=")?)?;
+        builder.t_cost(next_cost("t=")?)?;
+        builder.p_cost(next_cost("p=")?)?;
+
+        if costs.next().is_some() {
+            return Err(Error::EncodingInvalid);
+        }
+
+        let salt = decode_b64(next_field()?)?;
+        let hash = decode_b64(next_field()?)?;
+
+        if fields.next().is_some() {
+            return Err(Error::EncodingInvalid);
+        }
+
+        builder.output_len(hash.len())?;
+        let params: Params = builder.params()?;
+
+        Ok((Argon2::new(algorithm, version, params), salt, hash))
+    }
+}
+
+impl Argon2<'_> {
+    /// Encode a salt and hash computed with this context in the encoded
+    /// format of the reference C implementation, i.e.
+    /// `$argon2id$v=19$m=65536,t=3,p=4$<salt>$<hash>`.
+    ///
+    /// The salt and hash are encoded as standard Base64 without padding, so
+    /// the result matches the output of the reference implementation's
+    /// `argon2_encode` byte-for-byte.
+    pub fn to_encoded(&self, salt: &[u8], hash: &[u8]) -> String {
+        let mut encoded = String::new();
+
+        // Writing to a `String` can't fail
+        let _ = write!(
+            encoded,
+            "${}$v={}$m={},t={},p={}$",
+            self.algorithm.as_str(),
+            u32::from(self.version),
+            self.params.m_cost(),
+            self.params.t_cost(),
+            self.params.p_cost()
+        );
+        encode_b64(salt, &mut encoded);
+        encoded.push('$');
+        encode_b64(hash, &mut encoded);
+
+        encoded
+    }
+}
+
+/// Decode a decimal number without sign or leading zeroes.
+fn decode_decimal(s: &str) -> Result<u32> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) || (s.len() > 1 && s.starts_with('0'))
+    {
+        return Err(Error::EncodingInvalid);
+    }
+
+    s.parse().map_err(|_| Error::EncodingInvalid)
+}
+
+/// Decode standard Base64, with or without padding.
+fn decode_b64(s: &str) -> Result<Vec<u8>> {
+    let s = s.trim_end_matches('=');
+    let mut buf = vec![0u8; s.len() * 3 / 4];
+    let len = Base64Unpadded::decode(s, &mut buf)?.len();
+    buf.truncate(len);
+    Ok(buf)
+}
+
+/// Append the standard Base64 encoding of `input`, without padding.
+fn encode_b64(input: &[u8], encoded: &mut String) {
+    let mut buf = vec![0u8; Base64Unpadded::encoded_len(input)];
+    encoded.push_str(Base64Unpadded::encode(input, &mut buf).expect("buffer too small"));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Algorithm, Argon2, Error, Version};
+    use hex_literal::hex;
+
+    /// Test vector from the reference implementation's `test.c`.
+    const EXAMPLE_V0X13: &str =
+        "$argon2i$v=19$m=256,t=2,p=1$c29tZXNhbHQ$iekCn0Y3spW+sCcFanM2xBT63UP2sghkUoHLIUpWRS8";
+
+    #[test]
+    fn from_encoded() {
+        let (argon2, salt, hash) = Argon2::from_encoded(EXAMPLE_V0X13).unwrap();
+        assert_eq!(argon2.algorithm, Algorithm::Argon2i);
+        assert_eq!(argon2.version, Version::V0x13);
+        assert_eq!(argon2.params().m_cost(), 256);
+        assert_eq!(argon2.params().t_cost(), 2);
+        assert_eq!(argon2.params().p_cost(), 1);
+        assert_eq!(argon2.params().output_len(), Some(32));
+        assert_eq!(salt, b"somesalt");
+        assert_eq!(
+            hash,
+            hex!("89e9029f4637b295beb027056a7336c414fadd43f6b208645281cb214a56452f")
+        );
+
+        let mut out = [0u8; 32];
+        argon2
+            .hash_password_into(b"password", &salt, &mut out)
+            .unwrap();
+        assert_eq!(out[..], hash[..]);
+    }
+
+    #[test]
+    fn to_encoded() {
+        let (argon2, salt, hash) = Argon2::from_encoded(EXAMPLE_V0X13).unwrap();
+        assert_eq!(argon2.to_encoded(&salt, &hash), EXAMPLE_V0X13);
+
+        let mut out = [0u8; 32];
+        argon2
+            .hash_password_into(b"password", b"somesalt", &mut out)
+            .unwrap();
+        assert_eq!(argon2.to_encoded(b"somesalt", &out), EXAMPLE_V0X13);
+    }
+
+    #[test]
+    fn from_encoded_without_version() {
+        let (argon2, salt, hash) = Argon2::from_encoded(
+            "$argon2i$m=256,t=2,p=1$c29tZXNhbHQ$/U3YPXYsSb3q9XxHvc0MLxur+GP960kN9j7emXX8zwY",
+        )
+        .unwrap();
+        assert_eq!(argon2.version, Version::V0x10);
+
+        let mut out = [0u8; 32];
+        argon2
+            .hash_password_into(b"password", &salt, &mut out)
+            .unwrap();
+        assert_eq!(out[..], hash[..]);
+    }
+
+    #[test]
+    fn from_encoded_with_padding() {
+        let (_, salt, hash) = Argon2::from_encoded(
+            "$argon2i$v=19$m=256,t=2,p=1$c29tZXNhbHQ=$iekCn0Y3spW+sCcFanM2xBT63UP2sghkUoHLIUpWRS8=",
+        )
+        .unwrap();
+        assert_eq!(salt, b"somesalt");
+        assert_eq!(hash.len(), 32);
+    }
+
+    #[test]
+    fn from_encoded_invalid() {
+        for s in [
+            "",
+            "argon2i$v=19$m=256,t=2,p=1$c29tZXNhbHQ$iekCn0Y3spW+sCcFanM2xBT63UP2sghkUoHLIUpWRS8",
+            "$argon2i$v=19$m=256,t=2$c29tZXNhbHQ$iekCn0Y3spW+sCcFanM2xBT63UP2sghkUoHLIUpWRS8",
+            "$argon2i$v=19$t=2,m=256,p=1$c29tZXNhbHQ$iekCn0Y3spW+sCcFanM2xBT63UP2sghkUoHLIUpWRS8",
+            "$argon2i$v=19$m=256,t=2,p=1,x=1$c29tZXNhbHQ$iekCn0Y3spW+sCcFanM2xBT63UP2sghkUoHLIUpWRS8",
+            "$argon2i$v=19$m=+256,t=2,p=1$c29tZXNhbHQ$iekCn0Y3spW+sCcFanM2xBT63UP2sghkUoHLIUpWRS8",
+            "$argon2i$v=19$m=256,t=2,p=1$c29tZXNhbHQ",
+            "$argon2i$v=19$m=256,t=2,p=1$c29tZXNhbHQ$iekCn0Y3spW+sCcFanM2xBT63UP2sghkUoHLIUpWRS8$",
+        ] {
+            assert_eq!(Argon2::from_encoded(s).err(), Some(Error::EncodingInvalid), "{}", s);
+        }
+
+        assert_eq!(
+            Argon2::from_encoded(
+                "$argon2x$v=19$m=256,t=2,p=1$c29tZXNhbHQ$iekCn0Y3spW+sCcFanM2xBT63UP2sghkUoHLIUpWRS8"
+            )
+            .err(),
+            Some(Error::AlgorithmInvalid)
+        );
+        assert_eq!(
+            Argon2::from_encoded(
+                "$argon2i$v=18$m=256,t=2,p=1$c29tZXNhbHQ$iekCn0Y3spW+sCcFanM2xBT63UP2sghkUoHLIUpWRS8"
+            )
+            .err(),
+            Some(Error::VersionUnsupported(18))
+        );
+    }
+}
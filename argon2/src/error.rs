@@ -9,35 +9,77 @@ use password_hash::errors::InvalidValue;
 pub type Result<T> = core::result::Result<T, Error>;
 
 /// Error type.
+///
+/// Errors only ever carry the values of public parameters, such as a version
+/// number. They never contain passwords, secrets, salts or associated data, so
+/// they are safe to log.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Error {
     /// Associated data is too long.
     AdTooLong,
 
     /// Algorithm identifier invalid.
+    ///
+    /// Unlike [`Error::VersionUnsupported`], this doesn't carry the rejected
+    /// value, as identifiers are strings which can't be stored without
+    /// allocating.
     AlgorithmInvalid,
 
     /// "B64" encoding is invalid.
     B64Encoding(base64ct::Error),
 
+    /// Hashing was cancelled before it completed.
+    Cancelled,
+
+    /// Encoded hash is invalid.
+    EncodingInvalid,
+
     /// Key ID is too long.
     KeyIdTooLong,
 
+    /// Memory cost exceeds the process-wide maximum set with
+    /// `set_global_max_memory`.
+    MemoryExceedsPolicy,
+
+    /// Memory could not be locked into RAM.
+    MemoryLockFailed,
+
+    /// Memory could not be mapped.
+    MemoryMapFailed,
+
     /// Memory cost is too small.
     MemoryTooLittle,
 
     /// Memory cost is too large.
     MemoryTooMuch,
 
+    /// Output length differs from the one configured in the parameters.
+    OutputLenMismatch {
+        /// Output length configured in the parameters
+        expected: usize,
+
+        /// Length of the output buffer
+        actual: usize,
+    },
+
     /// Output is too short.
     OutputTooShort,
 
     /// Output is too long.
     OutputTooLong,
 
+    /// Password does not match the hash.
+    PwdInvalid,
+
+    /// Password could not be read.
+    PwdReadFailed,
+
     /// Password is too long.
     PwdTooLong,
 
+    /// Resume state is malformed or doesn't match the parameters.
+    ResumeStateInvalid,
+
     /// Salt is too short.
     SaltTooShort,
 
@@ -47,6 +89,9 @@ pub enum Error {
     /// Secret is too long.
     SecretTooLong,
 
+    /// Known-answer self-test produced an unexpected output.
+    SelfTestFailed,
+
     /// Not enough threads.
     ThreadsTooFew,
 
@@ -56,8 +101,14 @@ pub enum Error {
     /// Time cost is too small.
     TimeTooSmall,
 
-    /// Invalid version
+    /// Version is not a well-formed version number.
     VersionInvalid,
+
+    /// Version number is well-formed but not supported.
+    VersionUnsupported(u32),
+
+    /// Work units exceed the configured maximum.
+    WorkExceedsLimit,
 }
 
 impl fmt::Display for Error {
@@ -66,19 +117,37 @@ impl fmt::Display for Error {
             Error::AdTooLong => "associated data is too long",
             Error::AlgorithmInvalid => "algorithm identifier invalid",
             Error::B64Encoding(inner) => return write!(f, "B64 encoding invalid: {}", inner),
+            Error::Cancelled => "hashing was cancelled",
+            Error::EncodingInvalid => "encoded hash is invalid",
             Error::KeyIdTooLong => "key ID is too long",
+            Error::MemoryExceedsPolicy => "memory cost exceeds the configured maximum",
+            Error::MemoryLockFailed => "failed to lock memory",
+            Error::MemoryMapFailed => "failed to map memory",
             Error::MemoryTooLittle => "memory cost is too small",
             Error::MemoryTooMuch => "memory cost is too large",
+            Error::OutputLenMismatch { expected, actual } => {
+                return write!(
+                    f,
+                    "output length is {} but the parameters require {}",
+                    actual, expected
+                )
+            }
             Error::OutputTooShort => "output is too short",
             Error::OutputTooLong => "output is too long",
+            Error::PwdInvalid => "password is invalid",
+            Error::PwdReadFailed => "failed to read password",
             Error::PwdTooLong => "password is too long",
+            Error::ResumeStateInvalid => "resume state is invalid",
             Error::SaltTooShort => "salt is too short",
             Error::SaltTooLong => "salt is too long",
             Error::SecretTooLong => "secret is too long",
+            Error::SelfTestFailed => "self-test failed",
             Error::ThreadsTooFew => "not enough threads",
             Error::ThreadsTooMany => "too many threads",
             Error::TimeTooSmall => "time cost is too small",
             Error::VersionInvalid => "invalid version",
+            Error::VersionUnsupported(id) => return write!(f, "unsupported version: {:#x}", id),
+            Error::WorkExceedsLimit => "work units exceed the configured maximum",
         })
     }
 }
@@ -97,22 +166,74 @@ impl From<Error> for password_hash::Error {
             Error::AdTooLong => InvalidValue::TooLong.param_error(),
             Error::AlgorithmInvalid => password_hash::Error::Algorithm,
             Error::B64Encoding(inner) => password_hash::Error::B64Encoding(inner),
+            Error::Cancelled => password_hash::Error::Crypto,
+            Error::EncodingInvalid => password_hash::Error::PhcStringInvalid,
             Error::KeyIdTooLong => InvalidValue::TooLong.param_error(),
+            Error::MemoryExceedsPolicy => InvalidValue::TooLong.param_error(),
+            Error::MemoryLockFailed => password_hash::Error::Crypto,
+            Error::MemoryMapFailed => password_hash::Error::Crypto,
             Error::MemoryTooLittle => InvalidValue::TooShort.param_error(),
             Error::MemoryTooMuch => InvalidValue::TooLong.param_error(),
+            Error::PwdInvalid => password_hash::Error::Password,
+            Error::PwdReadFailed => password_hash::Error::Password,
             Error::PwdTooLong => password_hash::Error::Password,
+            Error::OutputLenMismatch { expected, actual } if actual < expected => {
+                password_hash::Error::OutputTooShort
+            }
+            Error::OutputLenMismatch { .. } => password_hash::Error::OutputTooLong,
             Error::OutputTooShort => password_hash::Error::OutputTooShort,
             Error::OutputTooLong => password_hash::Error::OutputTooLong,
+            Error::ResumeStateInvalid => InvalidValue::Malformed.param_error(),
             Error::SaltTooShort => InvalidValue::TooShort.salt_error(),
             Error::SaltTooLong => InvalidValue::TooLong.salt_error(),
             Error::SecretTooLong => InvalidValue::TooLong.param_error(),
+            Error::SelfTestFailed => password_hash::Error::Crypto,
             Error::ThreadsTooFew => InvalidValue::TooShort.param_error(),
             Error::ThreadsTooMany => InvalidValue::TooLong.param_error(),
             Error::TimeTooSmall => InvalidValue::TooShort.param_error(),
             Error::VersionInvalid => password_hash::Error::Version,
+            Error::VersionUnsupported(_) => password_hash::Error::Version,
+            Error::WorkExceedsLimit => InvalidValue::TooLong.param_error(),
         }
     }
 }
 
 #[cfg(feature = "std")]
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::B64Encoding(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::Error;
+
+    #[test]
+    fn display_includes_context() {
+        use alloc::string::ToString;
+
+        assert_eq!(
+            Error::VersionUnsupported(0x12).to_string(),
+            "unsupported version: 0x12"
+        );
+        assert_eq!(
+            Error::B64Encoding(base64ct::Error::InvalidLength).to_string(),
+            "B64 encoding invalid: invalid Base64 length"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn source() {
+        use std::error::Error as _;
+
+        assert!(Error::B64Encoding(base64ct::Error::InvalidEncoding)
+            .source()
+            .is_some());
+        assert!(Error::VersionUnsupported(0x12).source().is_none());
+    }
+}
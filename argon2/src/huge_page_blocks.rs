@@ -0,0 +1,41 @@
+//! Memory blocks backed by an anonymous memory map using huge pages.
+
+use crate::{Block, MmapBlocks, Params, Result};
+
+/// Memory blocks backed by an anonymous memory map which is advised to use
+/// transparent huge pages.
+///
+/// On Linux this calls `madvise(MADV_HUGEPAGE)` on the mapping, which reduces
+/// TLB misses for large memory costs. If the kernel doesn't support
+/// transparent huge pages, or on other platforms, this behaves exactly like
+/// [`MmapBlocks`].
+#[cfg_attr(docsrs, doc(cfg(feature = "hugepages")))]
+pub struct HugePageBlocks {
+    /// Memory map
+    blocks: MmapBlocks,
+}
+
+impl HugePageBlocks {
+    /// Map the memory blocks required by the given [`Params`].
+    ///
+    /// Returns [`Error::MemoryMapFailed`][`crate::Error::MemoryMapFailed`]
+    /// if the memory couldn't be mapped. Failing to enable huge pages is not
+    /// an error.
+    pub fn new(params: &Params) -> Result<Self> {
+        let blocks = MmapBlocks::new(params)?;
+        blocks.advise_huge_pages();
+        Ok(Self { blocks })
+    }
+}
+
+impl AsRef<[Block]> for HugePageBlocks {
+    fn as_ref(&self) -> &[Block] {
+        self.blocks.as_ref()
+    }
+}
+
+impl AsMut<[Block]> for HugePageBlocks {
+    fn as_mut(&mut self) -> &mut [Block] {
+        self.blocks.as_mut()
+    }
+}
@@ -1,18 +1,27 @@
 //! Argon2 instance (i.e. state)
 
-use crate::{Algorithm, Argon2, Block, Error, Memory, Params, Result, Version, SYNC_POINTS};
-use blake2::{
-    digest::{self, Digest, Output, VariableOutput},
-    Blake2b512, Blake2bVar,
+use crate::{
+    block::CompressFn, variable_hash::blake2b_long, Algorithm, Argon2, Block, Error, Memory,
+    Params, Result, Version, SYNC_POINTS,
+};
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicBool, Ordering},
 };
 
 #[cfg(feature = "parallel")]
 use {
     alloc::vec::Vec,
     core::mem,
-    rayon::iter::{ParallelBridge, ParallelIterator},
+    rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator},
 };
 
+#[cfg(all(feature = "parallel-std", not(feature = "parallel")))]
+use std::sync::Barrier;
+
+#[cfg(feature = "stats")]
+use crate::stats::AccessStats;
+
 #[cfg(feature = "zeroize")]
 use zeroize::Zeroize;
 
@@ -20,18 +29,15 @@ use zeroize::Zeroize;
 /// to generate reference block positions
 const ADDRESSES_IN_BLOCK: u32 = 128;
 
-/// Output size of BLAKE2b in bytes
-const BLAKE2B_OUTBYTES: usize = 64;
-
 /// Argon2 position: where we construct the block right now.
 ///
 /// Used to distribute work between threads.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-struct Position {
-    pass: u32,
-    lane: u32,
-    slice: u32,
-    index: u32,
+pub(crate) struct Position {
+    pub(crate) pass: u32,
+    pub(crate) lane: u32,
+    pub(crate) slice: u32,
+    pub(crate) index: u32,
 }
 
 /// Argon2 instance: memory buffer, number of passes, amount of memory, type,
@@ -60,37 +66,140 @@ pub(crate) struct Instance<'a> {
 
     /// Argon2 type
     alg: Algorithm,
+
+    /// Compression function implementation selected for the current CPU
+    compress: CompressFn,
+
+    /// Statistics to record the reference blocks into, if any
+    #[cfg(feature = "stats")]
+    stats: Option<&'a mut AccessStats>,
 }
 
 impl<'a> Instance<'a> {
-    /// Hash the given inputs with Argon2, writing the output into the
-    /// provided buffer.
+    /// Hash the given inputs with Argon2, passing the final block to
+    /// `make_tag`, which computes the output from it.
+    ///
+    /// If `cancel` is provided, it is checked at every synchronization point
+    /// and hashing is aborted with [`Error::Cancelled`] once it is set.
+    ///
+    /// When the `zeroize` feature is enabled, the memory is zeroized before
+    /// returning, whether hashing succeeded or not.
     pub fn hash(
         context: &Argon2<'_>,
         alg: Algorithm,
-        initial_hash: Output<Blake2b512>,
+        initial_hash: [u8; 64],
         memory: Memory<'a>,
-        out: &mut [u8],
+        cancel: Option<&AtomicBool>,
+        make_tag: impl FnOnce(&[u8]) -> Result<()>,
     ) -> Result<()> {
-        let mut instance = Self::new(context, alg, initial_hash, memory)?;
-
-        // Filling memory
-        instance.fill_memory_blocks();
-
-        // Finalization
-        instance.finalize(out)
+        Self::new(context, alg, memory).run(initial_hash, cancel, make_tag)
     }
 
-    /// Hashes the inputs with BLAKE2b and creates first two blocks.
+    /// Hash the given inputs like [`Instance::hash`], recording the
+    /// reference blocks into `stats`.
     ///
-    /// Returns struct containing main memory with 2 blocks per lane initialized.
+    /// The lanes are filled on the calling thread, so that `stats` doesn't
+    /// need to be shared between threads.
+    #[cfg(feature = "stats")]
+    pub(crate) fn hash_with_stats(
+        context: &Argon2<'_>,
+        initial_hash: [u8; 64],
+        memory: Memory<'a>,
+        stats: &'a mut AccessStats,
+        make_tag: impl FnOnce(&[u8]) -> Result<()>,
+    ) -> Result<()> {
+        let mut instance = Self::new(context, context.algorithm, memory);
+        instance.threads = 1;
+        instance.stats = Some(stats);
+        instance.run(initial_hash, None, make_tag)
+    }
+
+    /// Compute the hash from the initial hash, see [`Instance::hash`].
     #[allow(unused_mut)]
-    fn new(
+    fn run(
+        mut self,
+        mut initial_hash: [u8; 64],
+        cancel: Option<&AtomicBool>,
+        make_tag: impl FnOnce(&[u8]) -> Result<()>,
+    ) -> Result<()> {
+        // GENKAT note: this is where `initial_kat` would be called
+
+        // Creating first blocks, we always have at least two blocks in a slice
+        let result = self
+            .fill_first_blocks(&initial_hash)
+            // Filling memory
+            .and_then(|()| self.fill_memory_blocks(cancel))
+            // Finalization
+            .and_then(|()| self.finalize(make_tag));
+
+        #[cfg(feature = "zeroize")]
+        {
+            initial_hash.zeroize();
+            self.memory.zeroize();
+        }
+
+        result
+    }
+
+    /// Initialize the first two blocks of every lane of the given memory
+    /// from the initial hash.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn fill_first(
+        context: &Argon2<'_>,
+        initial_hash: &[u8],
+        memory: Memory<'a>,
+    ) -> Result<()> {
+        Self::new(context, context.algorithm, memory).fill_first_blocks(initial_hash)
+    }
+
+    /// Fill the slices of the given range of synchronization points, see
+    /// [`Instance::fill_sync_points`].
+    #[cfg(feature = "alloc")]
+    pub(crate) fn fill_range(
+        context: &Argon2<'_>,
+        memory: Memory<'a>,
+        sync_points: Range<u64>,
+    ) -> Result<()> {
+        Self::new(context, context.algorithm, memory).fill_sync_points(sync_points, None)
+    }
+
+    /// Finalize completely filled memory, passing the final block to
+    /// `make_tag`.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn finalize_filled(
+        context: &Argon2<'_>,
+        memory: Memory<'a>,
+        make_tag: impl FnOnce(&[u8]) -> Result<()>,
+    ) -> Result<()> {
+        Self::new(context, context.algorithm, memory).finalize(make_tag)
+    }
+
+    /// Fill all segments of the given memory, whose first two blocks in each
+    /// lane must already be initialized.
+    #[cfg(feature = "unstable-internals")]
+    pub(crate) fn fill(context: &Argon2<'_>, memory: Memory<'a>) -> Result<()> {
+        Self::new(context, context.algorithm, memory).fill_memory_blocks(None)
+    }
+
+    /// Fill a single segment of the given memory.
+    #[cfg(feature = "unstable-internals")]
+    pub(crate) fn fill_single_segment(
         context: &Argon2<'_>,
-        alg: Algorithm,
-        mut initial_hash: Output<Blake2b512>,
         memory: Memory<'a>,
-    ) -> Result<Self> {
+        pass: u32,
+        lane: u32,
+        slice: u32,
+    ) {
+        Self::new(context, context.algorithm, memory).fill_segment(Position {
+            pass,
+            lane,
+            slice,
+            index: 0,
+        });
+    }
+
+    /// Creates an instance for filling the given memory.
+    fn new(context: &Argon2<'_>, alg: Algorithm, memory: Memory<'a>) -> Self {
         let lane_length = memory.segment_length() * SYNC_POINTS;
 
         let mut instance = Instance {
@@ -99,96 +208,202 @@ impl<'a> Instance<'a> {
             passes: context.params.t_cost(),
             lane_length,
             lanes: context.params.lanes(),
-            threads: context.params.p_cost(),
+            threads: context
+                .params
+                .p_cost()
+                .min(context.max_threads.try_into().unwrap_or(u32::MAX)),
             alg,
+            compress: Block::compress_fn(),
+            #[cfg(feature = "stats")]
+            stats: None,
         };
 
         if instance.threads > instance.lanes {
             instance.threads = instance.lanes;
         }
 
-        // GENKAT note: this is where `initial_kat` would be called
-
-        // Creating first blocks, we always have at least two blocks in a slice
-        instance.fill_first_blocks(&initial_hash)?;
-
-        #[cfg(feature = "zeroize")]
-        initial_hash.zeroize();
-
-        Ok(instance)
+        instance
     }
 
-    /// Create multiple mutable references for the current instance, one for every lane
+    /// Create multiple mutable references for the current instance, one for every thread
     #[cfg(feature = "parallel")]
     #[allow(unsafe_code)]
-    unsafe fn mut_self_refs(&mut self) -> Vec<usize> {
-        let lanes = self.lanes;
+    unsafe fn mut_self_refs(&mut self, threads: u32) -> Vec<usize> {
         // This transmute can be skipped when a scoped threadpool is used (or when `spawn_unchecked()` gets stabilised)
         let this = mem::transmute::<_, &mut Instance<'static>>(self);
         let this: *mut Instance<'static> = this;
         let this = this as usize;
 
         // Dereference the raw pointer multiple times to create multiple mutable references
-        core::iter::repeat(this).take(lanes as usize).collect()
+        core::iter::repeat(this).take(threads as usize).collect()
     }
 
     #[cfg(feature = "parallel")]
-    fn fill_memory_blocks_par(&mut self) {
-        for r in 0..self.passes {
-            for s in 0..SYNC_POINTS {
-                // Safety: - All threads that receive a references will be joined before the item gets dropped
-                //         - All the read and write operations *shouldn't* overlap
-                #[allow(unsafe_code)]
-                let self_refs = unsafe { self.mut_self_refs() };
-
-                (0..self.lanes)
-                    .zip(self_refs)
-                    .par_bridge()
-                    .for_each(|(l, self_ref)| {
-                        #[allow(unsafe_code)]
-                        let self_ref = unsafe { &mut *(self_ref as *mut Instance<'static>) };
+    fn fill_memory_blocks_par(
+        &mut self,
+        sync_points: Range<u64>,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<()> {
+        let lanes = self.lanes;
 
+        // Group the lanes into at most one chunk per thread of the pool, so
+        // that a large number of lanes doesn't create many tiny tasks
+        let chunks = self
+            .threads
+            .min(rayon::current_num_threads().try_into().unwrap_or(u32::MAX))
+            .max(1);
+
+        for (r, s) in pass_and_slice(sync_points) {
+            check_cancelled(cancel)?;
+
+            // Safety: - All threads that receive a references will be joined before the item gets dropped
+            //         - All the read and write operations *shouldn't* overlap
+            #[allow(unsafe_code)]
+            let self_refs = unsafe { self.mut_self_refs(chunks) };
+
+            // Each task fills the segments of a contiguous chunk of lanes.
+            // Every segment of the slice belongs to exactly one chunk.
+            (0..chunks)
+                .into_par_iter()
+                .zip(self_refs)
+                .for_each(|(c, self_ref)| {
+                    #[allow(unsafe_code)]
+                    let self_ref = unsafe { &mut *(self_ref as *mut Instance<'static>) };
+
+                    for l in lane_chunk(lanes, chunks, c) {
                         self_ref.fill_segment(Position {
                             pass: r,
                             lane: l,
                             slice: s,
                             index: 0,
                         });
-                    });
+                    }
+                });
+        }
+
+        Ok(())
+    }
+
+    /// Fill the memory using scoped `std` threads, one per available core
+    /// up to the number of lanes.
+    ///
+    /// Each thread fills the segments of every `n`th lane, where `n` is the
+    /// number of threads, and all threads wait on a barrier at every
+    /// synchronization point.
+    ///
+    /// `std::thread::scope` requires Rust 1.63, above the crate's MSRV.
+    #[cfg(all(feature = "parallel-std", not(feature = "parallel")))]
+    #[allow(unsafe_code)]
+    #[clippy::msrv = "1.63"]
+    fn fill_memory_blocks_std(
+        &mut self,
+        sync_points: Range<u64>,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<()> {
+        let workers = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(self.threads as usize);
+
+        let lanes = self.lanes;
+        let barrier = Barrier::new(workers);
+        let cancelled = AtomicBool::new(false);
+        let this = self as *mut Instance<'a> as usize;
+
+        std::thread::scope(|scope| {
+            for worker in 0..workers {
+                let barrier = &barrier;
+                let cancelled = &cancelled;
+                let sync_points = sync_points.clone();
+
+                scope.spawn(move || {
+                    for (r, s) in pass_and_slice(sync_points) {
+                        // A single thread checks for cancellation so that
+                        // all of them agree on whether to continue
+                        if barrier.wait().is_leader() {
+                            cancelled.store(check_cancelled(cancel).is_err(), Ordering::Relaxed);
+                        }
+
+                        barrier.wait();
+
+                        if cancelled.load(Ordering::Relaxed) {
+                            return;
+                        }
+
+                        for l in (worker as u32..lanes).step_by(workers) {
+                            // Safety: - All threads are joined before the scope ends
+                            //         - The read and write operations of segments
+                            //           in the same slice *shouldn't* overlap
+                            let this = unsafe { &mut *(this as *mut Instance<'a>) };
+
+                            this.fill_segment(Position {
+                                pass: r,
+                                lane: l,
+                                slice: s,
+                                index: 0,
+                            });
+                        }
+                    }
+                });
             }
+        });
 
-            // GENKAT note: this is where `internal_kat` would be called
+        if cancelled.into_inner() {
+            Err(Error::Cancelled)
+        } else {
+            Ok(())
         }
     }
 
     /// Function that fills the entire memory t_cost times based on the first two
     /// blocks in each lane
-    fn fill_memory_blocks(&mut self) {
+    fn fill_memory_blocks(&mut self, cancel: Option<&AtomicBool>) -> Result<()> {
+        let sync_points = 0..u64::from(self.passes) * u64::from(SYNC_POINTS);
+        self.fill_sync_points(sync_points, cancel)
+    }
+
+    /// Fill the slices of the given range of synchronization points, counted
+    /// from the first slice of the first pass.
+    ///
+    /// All slices before the start of the range must have been filled.
+    fn fill_sync_points(
+        &mut self,
+        sync_points: Range<u64>,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<()> {
         #[cfg(feature = "parallel")]
         if self.threads > 1 {
-            self.fill_memory_blocks_par();
-            return;
+            return self.fill_memory_blocks_par(sync_points, cancel);
+        }
+
+        #[cfg(all(feature = "parallel-std", not(feature = "parallel")))]
+        if self.threads > 1 {
+            return self.fill_memory_blocks_std(sync_points, cancel);
         }
 
         // Single-threaded version for p=1 case
-        for r in 0..self.passes {
-            for s in 0..SYNC_POINTS {
-                for l in 0..self.lanes {
-                    self.fill_segment(Position {
-                        pass: r,
-                        lane: l,
-                        slice: s,
-                        index: 0,
-                    });
-                }
+        for (r, s) in pass_and_slice(sync_points) {
+            check_cancelled(cancel)?;
+
+            for l in 0..self.lanes {
+                self.fill_segment(Position {
+                    pass: r,
+                    lane: l,
+                    slice: s,
+                    index: 0,
+                });
             }
 
-            // GENKAT note: this is where `internal_kat` would be called
+            // GENKAT note: this is where `internal_kat` would be called after
+            // the last slice of a pass
         }
+
+        Ok(())
     }
 
     /// XORing the last block of each lane, hashing it, making the tag.
-    fn finalize(&mut self, out: &mut [u8]) -> Result<()> {
+    ///
+    /// `make_tag` is called with the XORed block, and hashes it into the tag.
+    fn finalize(&mut self, make_tag: impl FnOnce(&[u8]) -> Result<()>) -> Result<()> {
         let mut blockhash = self.memory.get_block((self.lane_length - 1) as usize);
 
         // XOR the last blocks
@@ -198,13 +413,10 @@ impl<'a> Instance<'a> {
         }
 
         // Hash the result
-        let mut blockhash_bytes = [0u8; Block::SIZE];
+        #[allow(unused_mut)]
+        let mut blockhash_bytes = blockhash.to_le_bytes();
 
-        for (chunk, v) in blockhash_bytes.chunks_mut(8).zip(blockhash.iter()) {
-            chunk.copy_from_slice(&v.to_le_bytes())
-        }
-
-        blake2b_long(&[&blockhash_bytes], out)?;
+        let result = make_tag(&blockhash_bytes);
 
         #[cfg(feature = "zeroize")]
         blockhash.zeroize();
@@ -212,7 +424,7 @@ impl<'a> Instance<'a> {
         #[cfg(feature = "zeroize")]
         blockhash_bytes.zeroize();
 
-        Ok(())
+        result
     }
 
     /// Function creates first 2 blocks per lane
@@ -236,34 +448,23 @@ impl<'a> Instance<'a> {
     /// Function that fills the segment using previous segments
     // TODO(tarcieri): optimized implementation (i.e. from opt.c instead of ref.c)
     fn fill_segment(&mut self, mut position: Position) {
-        let mut address_block = Block::default();
-        let mut input_block = Block::default();
-        let zero_block = Block::default();
-
-        let data_independent_addressing = (self.alg == Algorithm::Argon2i)
-            || (self.alg == Algorithm::Argon2id
-                && (position.pass == 0)
-                && (position.slice < SYNC_POINTS / 2));
-
-        if data_independent_addressing {
-            input_block[0] = position.pass as u64;
-            input_block[1] = position.lane as u64;
-            input_block[2] = position.slice as u64;
-            input_block[3] = self.memory.len() as u64;
-            input_block[4] = self.passes as u64;
-            input_block[5] = self.alg as u64;
-        }
-
-        let mut starting_index = 0;
-
-        if position.pass == 0 && position.slice == 0 {
-            starting_index = 2; // we have already generated the first two blocks
-
-            // Don't forget to generate the first block of addresses
-            if data_independent_addressing {
-                next_addresses(&mut address_block, &mut input_block, &zero_block);
-            }
-        }
+        // Argon2id uses data-independent addressing for the first half of the
+        // first pass, so the address block logic is needed by every variant
+        let mut addresses = self
+            .alg
+            .uses_data_independent_addressing(position.pass as usize, position.slice as usize)
+            .then(|| {
+                AddressGenerator::new(
+                    self.compress,
+                    position,
+                    self.memory.len(),
+                    self.passes,
+                    self.alg,
+                )
+            });
+
+        // We have already generated the first two blocks
+        let starting_index = first_index(position);
 
         // Offset of the current block
         let mut curr_offset = position.lane * self.lane_length
@@ -286,173 +487,402 @@ impl<'a> Instance<'a> {
 
             // 1.2 Computing the index of the reference block
             // 1.2.1 Taking pseudo-random value from the previous block
-            let pseudo_rand = if data_independent_addressing {
-                if i % ADDRESSES_IN_BLOCK == 0 {
-                    next_addresses(&mut address_block, &mut input_block, &zero_block);
-                }
-                address_block[(i % ADDRESSES_IN_BLOCK) as usize]
-            } else {
-                self.memory.get_block(prev_offset as usize)[0]
+            let pseudo_rand = match addresses.as_mut() {
+                Some(addresses) => addresses.pseudo_rand(i),
+                None => self.memory.get_block(prev_offset as usize)[0],
             };
 
-            // 1.2.2 Computing the lane of the reference block
-            let mut ref_lane = (pseudo_rand >> 32) as u32 % self.lanes;
-
-            if position.pass == 0 && position.slice == 0 {
-                // Can not reference other lanes yet
-                ref_lane = position.lane;
-            }
-
-            // 1.2.3 Computing the number of possible reference block within the lane.
+            // 1.2.2 Computing the lane and index of the reference block
             position.index = i;
 
-            let ref_index = self.index_alpha(
+            let (ref_lane, ref_index) = reference_position(
+                self.lanes,
+                self.lane_length,
+                self.memory.segment_length(),
                 position,
-                (pseudo_rand & 0xFFFFFFFF) as u32,
-                ref_lane == position.lane,
+                pseudo_rand,
             );
 
             // 2 Creating a new block
-            let ref_block = self
-                .memory
-                .get_block((self.lane_length * ref_lane + ref_index) as usize);
+            let ref_offset = (self.lane_length * ref_lane + ref_index) as usize;
+
+            #[cfg(feature = "stats")]
+            if let Some(stats) = self.stats.as_mut() {
+                stats.record(ref_offset);
+            }
+
+            let ref_block = self.memory.get_block(ref_offset);
             let prev_block = self.memory.get_block(prev_offset as usize);
 
+            let result = (self.compress)(&prev_block, &ref_block);
+            let curr_block = self.memory.get_block_mut(curr_offset as usize);
+
             // version 1.2.1 and earlier: overwrite, not XOR
-            let without_xor = self.version == Version::V0x10 || position.pass == 0;
-            self.memory.get_block_mut(curr_offset as usize).fill_block(
-                prev_block,
-                ref_block,
-                !without_xor,
-            );
+            if self.version == Version::V0x10 || position.pass == 0 {
+                *curr_block = result;
+            } else {
+                *curr_block ^= result;
+            }
 
             curr_offset += 1;
             prev_offset += 1;
         }
     }
+}
 
-    /// Computes absolute position of reference block in the lane following a skewed
-    /// distribution and using a pseudo-random value as input.
-    ///
-    /// # Params
-    /// - `position`: Pointer to the current position
-    /// - `pseudo_rand`: 32-bit pseudo-random value used to determine the position
-    /// - `same_lane`: Indicates if the block will be taken from the current lane.
-    ///                If so we can reference the current segment.
-    fn index_alpha(&self, position: Position, pseudo_rand: u32, same_lane: bool) -> u32 {
-        // Pass 0:
-        // - This lane: all already finished segments plus already constructed
-        //   blocks in this segment
-        // - Other lanes: all already finished segments
-        //
-        // Pass 1+:
-        // - This lane: (SYNC_POINTS - 1) last segments plus already constructed
-        //   blocks in this segment
-        // - Other lanes : (SYNC_POINTS - 1) last segments
-        let reference_area_size = if 0 == position.pass {
-            // First pass
-            if position.slice == 0 {
-                // First slice
-                position.index - 1 // all but the previous
-            } else if same_lane {
-                // The same lane => add current segment
-                position.slice * self.memory.segment_length() + position.index - 1
-            } else {
-                position.slice * self.memory.segment_length()
-                    - if position.index == 0 { 1 } else { 0 }
-            }
-        } else {
-            // Second pass
-            if same_lane {
-                self.lane_length - self.memory.segment_length() + position.index - 1
-            } else {
-                self.lane_length
-                    - self.memory.segment_length()
-                    - if position.index == 0 { 1 } else { 0 }
-            }
+/// Generator of the pseudo-random values of a segment which is filled with
+/// data-independent addressing, i.e. the first 32 bits of every address in
+/// a block of addresses computed from a counter.
+pub(crate) struct AddressGenerator {
+    /// Compression function implementation
+    compress: CompressFn,
+
+    /// Current block of addresses
+    address_block: Block,
+
+    /// Input of the address computation, including the counter
+    input_block: Block,
+
+    /// All-zero block
+    zero_block: Block,
+}
+
+impl AddressGenerator {
+    /// Create the generator for the segment at `position` of a memory of
+    /// `memory_blocks` blocks filled in `passes` passes with `alg`.
+    pub(crate) fn new(
+        compress: CompressFn,
+        position: Position,
+        memory_blocks: usize,
+        passes: u32,
+        alg: Algorithm,
+    ) -> Self {
+        let mut input_block = Block::default();
+        input_block[0] = position.pass as u64;
+        input_block[1] = position.lane as u64;
+        input_block[2] = position.slice as u64;
+        input_block[3] = memory_blocks as u64;
+        input_block[4] = passes as u64;
+        input_block[5] = alg as u64;
+
+        let mut generator = Self {
+            compress,
+            address_block: Block::default(),
+            input_block,
+            zero_block: Block::default(),
         };
 
-        // 1.2.4. Mapping pseudo_rand to 0..<reference_area_size-1> and produce
-        // relative position
-        let mut relative_position = pseudo_rand as u64;
-        relative_position = (relative_position * relative_position) >> 32;
-        let relative_position = reference_area_size
-            - 1
-            - (((reference_area_size as u64 * relative_position) >> 32) as u32);
+        // Don't forget to generate the first block of addresses, as the
+        // first segment starts past its first index
+        if first_index(position) != 0 {
+            generator.next_block();
+        }
 
-        // 1.2.5 Computing starting position
-        let mut start_position = 0;
+        generator
+    }
 
-        if position.pass != 0 {
-            start_position = if position.slice == SYNC_POINTS - 1 {
-                0
-            } else {
-                (position.slice + 1) * self.memory.segment_length()
-            }
+    /// Get the pseudo-random value for the block at `index` in the segment.
+    ///
+    /// Must be called for consecutive indices starting at the first index of
+    /// the segment.
+    pub(crate) fn pseudo_rand(&mut self, index: u32) -> u64 {
+        if index % ADDRESSES_IN_BLOCK == 0 {
+            self.next_block();
         }
 
-        // 1.2.6. Computing absolute position
-        (start_position + relative_position as u32) % self.lane_length
+        // The counter is the number of address blocks generated so far
+        debug_assert_eq!(
+            self.input_block[6],
+            u64::from(index / ADDRESSES_IN_BLOCK) + 1
+        );
+
+        self.address_block[(index % ADDRESSES_IN_BLOCK) as usize]
     }
-}
 
-/// Compute next addresses
-fn next_addresses(address_block: &mut Block, input_block: &mut Block, zero_block: &Block) {
-    input_block[6] += 1;
-    address_block.fill_block(*zero_block, *input_block, false);
-    address_block.fill_block(*zero_block, *address_block, false);
+    /// Compute the next block of addresses.
+    fn next_block(&mut self) {
+        next_addresses(
+            self.compress,
+            &mut self.address_block,
+            &mut self.input_block,
+            &self.zero_block,
+        );
+    }
 }
 
-/// BLAKE2b with an extended output, as described in the Argon2 paper
-fn blake2b_long(inputs: &[&[u8]], mut out: &mut [u8]) -> Result<()> {
-    if out.len() < Params::MIN_OUTPUT_LEN as usize {
-        return Err(Error::OutputTooLong);
+#[cfg(feature = "zeroize")]
+impl Drop for AddressGenerator {
+    fn drop(&mut self) {
+        self.address_block.zeroize();
+        self.input_block.zeroize();
+        self.zero_block.zeroize();
     }
+}
 
-    if out.len() > Params::MAX_OUTPUT_LEN as usize {
-        return Err(Error::OutputTooLong);
+/// Index of the first block in the segment at `position` which is computed
+/// from a reference block: the first two blocks of the first segment of
+/// every lane are computed from the initial hash instead.
+pub(crate) fn first_index(position: Position) -> u32 {
+    if position.pass == 0 && position.slice == 0 {
+        2
+    } else {
+        0
     }
+}
 
-    let outlen_bytes = (out.len() as u32).to_le_bytes();
-
-    if out.len() <= BLAKE2B_OUTBYTES {
-        use digest::Update;
+/// Compute the lane and the index within the lane of the reference block for
+/// the block at `position` from its pseudo-random value.
+pub(crate) fn reference_position(
+    lanes: u32,
+    lane_length: u32,
+    segment_length: u32,
+    position: Position,
+    pseudo_rand: u64,
+) -> (u32, u32) {
+    let ref_lane = if position.pass == 0 && position.slice == 0 {
+        // Can not reference other lanes yet
+        position.lane
+    } else {
+        (pseudo_rand >> 32) as u32 % lanes
+    };
+
+    let ref_index = index_alpha(
+        lane_length,
+        segment_length,
+        position,
+        (pseudo_rand & 0xFFFFFFFF) as u32,
+        ref_lane == position.lane,
+    );
+
+    (ref_lane, ref_index)
+}
 
-        let mut digest = Blake2bVar::new(out.len()).expect("`out` length is valid for Blake2bVar");
-        Update::update(&mut digest, &outlen_bytes);
+// `index_alpha` adds up to two lane lengths, which must not overflow `u32`
+const _: () = assert!(Params::MAX_M_COST <= u32::MAX / 2);
 
-        for input in inputs {
-            Update::update(&mut digest, input);
+/// Computes absolute position of reference block in the lane following a skewed
+/// distribution and using a pseudo-random value as input.
+///
+/// # Params
+/// - `position`: Pointer to the current position
+/// - `pseudo_rand`: 32-bit pseudo-random value used to determine the position
+/// - `same_lane`: Indicates if the block will be taken from the current lane.
+///                If so we can reference the current segment.
+///
+/// All arithmetic is done on `u32` (or `u64` for the mapping), which can't
+/// overflow as the lane length is at most [`Params::MAX_M_COST`], i.e. less
+/// than 2^28 blocks, on every target.
+fn index_alpha(
+    lane_length: u32,
+    segment_length: u32,
+    position: Position,
+    pseudo_rand: u32,
+    same_lane: bool,
+) -> u32 {
+    // Pass 0:
+    // - This lane: all already finished segments plus already constructed
+    //   blocks in this segment
+    // - Other lanes: all already finished segments
+    //
+    // Pass 1+:
+    // - This lane: (SYNC_POINTS - 1) last segments plus already constructed
+    //   blocks in this segment
+    // - Other lanes : (SYNC_POINTS - 1) last segments
+    let reference_area_size = if 0 == position.pass {
+        // First pass
+        if position.slice == 0 {
+            // First slice
+            position.index - 1 // all but the previous
+        } else if same_lane {
+            // The same lane => add current segment
+            position.slice * segment_length + position.index - 1
+        } else {
+            position.slice * segment_length - if position.index == 0 { 1 } else { 0 }
         }
-
-        digest
-            .finalize_variable(out)
-            .expect("`out` length is valid for Blake2bVar");
     } else {
-        let mut digest = Blake2b512::new();
-        digest.update(&outlen_bytes);
+        // Second pass
+        if same_lane {
+            lane_length - segment_length + position.index - 1
+        } else {
+            lane_length - segment_length - if position.index == 0 { 1 } else { 0 }
+        }
+    };
+
+    // 1.2.4. Mapping pseudo_rand to 0..<reference_area_size-1> and produce
+    // relative position
+    let mut relative_position = pseudo_rand as u64;
+    relative_position = (relative_position * relative_position) >> 32;
+    let relative_position =
+        reference_area_size - 1 - (((reference_area_size as u64 * relative_position) >> 32) as u32);
 
-        for input in inputs {
-            digest.update(input);
+    // 1.2.5 Computing starting position
+    let mut start_position = 0;
+
+    if position.pass != 0 {
+        start_position = if position.slice == SYNC_POINTS - 1 {
+            0
+        } else {
+            (position.slice + 1) * segment_length
         }
+    }
+
+    // 1.2.6. Computing absolute position
+    (start_position + relative_position as u32) % lane_length
+}
+
+/// Iterate over the `(pass, slice)` pairs of a range of synchronization
+/// points, counted from the first slice of the first pass.
+fn pass_and_slice(sync_points: Range<u64>) -> impl Iterator<Item = (u32, u32)> {
+    let slices = u64::from(SYNC_POINTS);
+    sync_points.map(move |i| ((i / slices) as u32, (i % slices) as u32))
+}
+
+/// Lanes of the `chunk`th of `chunks` contiguous chunks of `lanes` lanes
+/// whose sizes differ by at most one.
+#[cfg(feature = "parallel")]
+fn lane_chunk(lanes: u32, chunks: u32, chunk: u32) -> Range<u32> {
+    let bound = |c: u32| (u64::from(lanes) * u64::from(c) / u64::from(chunks)) as u32;
+    bound(chunk)..bound(chunk + 1)
+}
 
-        let mut hash = digest.finalize();
+/// Return [`Error::Cancelled`] if the `cancel` flag has been set.
+fn check_cancelled(cancel: Option<&AtomicBool>) -> Result<()> {
+    match cancel {
+        Some(flag) if flag.load(Ordering::Relaxed) => Err(Error::Cancelled),
+        _ => Ok(()),
+    }
+}
+
+/// Largest value of the address counter, i.e. the number of address blocks
+/// of the longest possible segment.
+const MAX_ADDRESS_COUNTER: u64 =
+    ((Params::MAX_M_COST / SYNC_POINTS + ADDRESSES_IN_BLOCK - 1) / ADDRESSES_IN_BLOCK) as u64;
 
-        let n = BLAKE2B_OUTBYTES / 2;
+/// Compute next addresses
+fn next_addresses(
+    compress: CompressFn,
+    address_block: &mut Block,
+    input_block: &mut Block,
+    zero_block: &Block,
+) {
+    // The counter restarts for every segment and is incremented once per
+    // `ADDRESSES_IN_BLOCK` blocks, so it is at most
+    // `ceil(segment_length / ADDRESSES_IN_BLOCK)`, far below `u64::MAX`
+    input_block[6] = input_block[6].wrapping_add(1);
+    debug_assert!(
+        input_block[6] <= MAX_ADDRESS_COUNTER,
+        "address counter out of range"
+    );
+    *address_block = compress(zero_block, input_block);
+    *address_block = compress(zero_block, address_block);
+}
 
-        let (chunk, tail) = out.split_at_mut(n);
-        out = tail;
-        chunk.copy_from_slice(&hash[..n]);
+#[cfg(test)]
+mod tests {
+    use super::{first_index, index_alpha, AddressGenerator, Position, ADDRESSES_IN_BLOCK};
+    use crate::{Algorithm, Block, Params, SYNC_POINTS};
 
-        while out.len() > BLAKE2B_OUTBYTES {
-            let (chunk, tail) = out.split_at_mut(n);
-            out = tail;
-            hash = Blake2b512::digest(&hash);
-            chunk.copy_from_slice(&hash[..n]);
+    /// The chunks of lanes cover every lane exactly once, in order.
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn lane_chunk() {
+        for lanes in [1, 2, 3, 7, 64, 255] {
+            for chunks in 1..=lanes.min(16) {
+                let mut next = 0;
+
+                for chunk in 0..chunks {
+                    let range = super::lane_chunk(lanes, chunks, chunk);
+                    assert_eq!(range.start, next);
+                    assert!(range.len() >= (lanes / chunks) as usize);
+                    assert!(range.len() <= (lanes / chunks + 1) as usize);
+                    next = range.end;
+                }
+
+                assert_eq!(next, lanes);
+            }
         }
+    }
 
-        Blake2bVar::digest_variable(&hash, out).expect("`out` length is valid for Blake2bVar");
+    /// The pseudo-random values of a segment spanning many address blocks
+    /// match an independent computation from the counter.
+    #[test]
+    fn address_generator_large_segment() {
+        let compress = Block::compress_fn();
+        let segment_length = 64 * ADDRESSES_IN_BLOCK + 37;
+        let memory_blocks = segment_length * SYNC_POINTS * 2;
+
+        for (pass, slice) in [(0, 0), (1, 2)] {
+            let position = Position {
+                pass,
+                lane: 1,
+                slice,
+                index: 0,
+            };
+            let mut addresses = AddressGenerator::new(
+                compress,
+                position,
+                memory_blocks as usize,
+                2,
+                Algorithm::Argon2i,
+            );
+
+            for index in first_index(position)..segment_length {
+                let mut input_block = Block::default();
+                input_block[0] = pass.into();
+                input_block[1] = 1;
+                input_block[2] = slice.into();
+                input_block[3] = memory_blocks.into();
+                input_block[4] = 2;
+                input_block[5] = Algorithm::Argon2i as u64;
+                input_block[6] = u64::from(index / ADDRESSES_IN_BLOCK) + 1;
+
+                let zero_block = Block::default();
+                let expected = compress(&zero_block, &compress(&zero_block, &input_block));
+                assert_eq!(
+                    addresses.pseudo_rand(index),
+                    expected[(index % ADDRESSES_IN_BLOCK) as usize]
+                );
+            }
+
+            assert_eq!(addresses.input_block[6], 65);
+        }
     }
 
-    Ok(())
+    /// Reference block positions for the largest possible lane are in
+    /// bounds, which would catch any overflow on 32-bit targets.
+    #[test]
+    fn index_alpha_max_lane_length() {
+        let segment_length = Params::MAX_M_COST / SYNC_POINTS;
+        let lane_length = segment_length * SYNC_POINTS;
+
+        for pass in [0, 1] {
+            for slice in 0..SYNC_POINTS {
+                for index in [0, 1, 2, segment_length - 1] {
+                    if pass == 0 && slice == 0 && index < 2 {
+                        continue;
+                    }
+
+                    let position = Position {
+                        pass,
+                        lane: 0,
+                        slice,
+                        index,
+                    };
+
+                    for pseudo_rand in [0, 1, u32::MAX / 2, u32::MAX] {
+                        for same_lane in [false, true] {
+                            let ref_index = index_alpha(
+                                lane_length,
+                                segment_length,
+                                position,
+                                pseudo_rand,
+                                same_lane,
+                            );
+                            assert!(ref_index < lane_length);
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,161 @@
+//! Unstable access to the memory-filling core of Argon2.
+//!
+//! This allows building on the lane and slice scheduling of Argon2 without
+//! its initial hashing and finalization, e.g. for a specialized KDF. It is
+//! not covered by semver guarantees and may change in any release.
+//!
+//! Argon2 memory consists of `p_cost` lanes of [`lane_length`] blocks each,
+//! stored one after another. Every lane is split into [`SLICES`] segments,
+//! which are filled in the following order for each pass:
+//!
+//! - all segments of a slice must be filled before any segment of the next
+//!   slice, and all slices of a pass before the next pass, as a segment
+//!   references blocks of previously filled segments in every lane;
+//! - the segments of different lanes in the same slice are independent of
+//!   each other and may be filled in any order.
+//!
+//! Before the first pass, the first two blocks of every lane must be
+//! initialized by the caller; they are computed from the initial hash in
+//! regular Argon2.
+
+use crate::{Argon2, Block, Error, Memory, Result, SYNC_POINTS};
+
+/// Number of slices, i.e. segments per lane.
+pub const SLICES: u32 = SYNC_POINTS;
+
+/// Number of blocks per lane for the given context's parameters.
+pub fn lane_length(argon2: &Argon2<'_>) -> u32 {
+    argon2.params.lane_length()
+}
+
+/// Position of a segment: the pass, lane and slice it belongs to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Segment {
+    /// Pass, less than `t_cost`
+    pub pass: u32,
+
+    /// Lane, less than `p_cost`
+    pub lane: u32,
+
+    /// Slice, less than [`SLICES`]
+    pub slice: u32,
+}
+
+/// Fill all segments of `memory_blocks` in order, for every pass.
+///
+/// The first two blocks of every lane must be initialized. This uses the
+/// same (possibly parallel) implementation as regular hashing. Returns
+/// [`Error::MemoryTooLittle`] if there are fewer blocks than required by the
+/// context's parameters.
+pub fn fill_memory(argon2: &Argon2<'_>, memory_blocks: &mut [Block]) -> Result<()> {
+    let memory = memory(argon2, memory_blocks)?;
+    crate::instance::Instance::fill(argon2, memory)
+}
+
+/// Fill a single segment of `memory_blocks`.
+///
+/// The segments it depends on must have been filled first, see the
+/// [module documentation](self). Returns [`Error::MemoryTooLittle`] if
+/// there are fewer blocks than required by the context's parameters.
+///
+/// # Panics
+///
+/// Panics if the segment's pass, lane or slice is out of range for the
+/// context's parameters.
+pub fn fill_segment(
+    argon2: &Argon2<'_>,
+    memory_blocks: &mut [Block],
+    segment: Segment,
+) -> Result<()> {
+    assert!(segment.pass < argon2.params.t_cost(), "pass out of range");
+    assert!(segment.lane < argon2.params.lanes(), "lane out of range");
+    assert!(segment.slice < SLICES, "slice out of range");
+
+    let memory = memory(argon2, memory_blocks)?;
+    crate::instance::Instance::fill_single_segment(
+        argon2,
+        memory,
+        segment.pass,
+        segment.lane,
+        segment.slice,
+    );
+    Ok(())
+}
+
+/// Get the memory to fill for the given context.
+fn memory<'a>(argon2: &Argon2<'_>, memory_blocks: &'a mut [Block]) -> Result<Memory<'a>> {
+    let memory_blocks = memory_blocks
+        .get_mut(..argon2.params.block_count())
+        .ok_or(Error::MemoryTooLittle)?;
+
+    Ok(Memory::new(memory_blocks, argon2.params.segment_length()))
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::{fill_memory, fill_segment, lane_length, Segment, SLICES};
+    use crate::{blake2b_long, Algorithm, Argon2, Block, Params, Version};
+
+    /// Reproduce regular hashing from the internals.
+    fn hash(argon2: &Argon2<'_>, fill: impl FnOnce(&mut [Block])) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        let initial_hash = argon2.initial_hash(b"password", b"somesalt", out.len());
+        let lane_length = lane_length(argon2);
+        let mut memory = vec![Block::default(); argon2.required_blocks()];
+        let mut bytes = [0u8; Block::SIZE];
+
+        for lane in 0..argon2.params().p_cost() {
+            for i in 0u32..2 {
+                blake2b_long(
+                    &[&initial_hash, &i.to_le_bytes(), &lane.to_le_bytes()],
+                    &mut bytes,
+                )
+                .unwrap();
+                memory[(lane * lane_length + i) as usize].load(&bytes);
+            }
+        }
+
+        fill(&mut memory);
+
+        let mut last = Block::default();
+        for lane in 0..argon2.params().p_cost() {
+            last ^= memory[(lane * lane_length + lane_length - 1) as usize];
+        }
+
+        for (chunk, word) in bytes.chunks_mut(8).zip(last.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+
+        blake2b_long(&[&bytes], &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn matches_regular_hashing() {
+        let params = Params::new(64, 2, 2, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(b"password", b"somesalt", &mut expected)
+            .unwrap();
+
+        assert_eq!(
+            hash(&argon2, |memory| fill_memory(&argon2, memory).unwrap()),
+            expected
+        );
+
+        let by_segment = hash(&argon2, |memory| {
+            for pass in 0..2 {
+                for slice in 0..SLICES {
+                    // Lanes of a slice can be filled in any order
+                    for lane in [1, 0] {
+                        let segment = Segment { pass, lane, slice };
+                        fill_segment(&argon2, memory, segment).unwrap();
+                    }
+                }
+            }
+        });
+        assert_eq!(by_segment, expected);
+    }
+}
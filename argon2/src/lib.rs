@@ -1,7 +1,14 @@
 #![no_std]
 // TODO(tarcieri): safe parallel implementation
 // See: https://github.com/RustCrypto/password-hashes/issues/154
-#![cfg_attr(not(feature = "parallel"), forbid(unsafe_code))]
+#![cfg_attr(
+    not(any(
+        feature = "parallel",
+        feature = "parallel-std",
+        feature = "secure-alloc"
+    )),
+    deny(unsafe_code)
+)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![doc = include_str!("../README.md")]
 #![doc(
@@ -65,22 +72,86 @@ extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
 
+mod access_pattern;
 mod algorithm;
+#[cfg(all(feature = "std", feature = "zeroize"))]
+mod arena_pool;
+#[cfg(feature = "tokio")]
+mod async_hash;
 mod block;
+mod builder;
+#[cfg(all(test, feature = "conformance-tests", feature = "alloc"))]
+mod conformance;
+#[cfg(feature = "alloc")]
+mod encoded;
 mod error;
+#[cfg(feature = "hugepages")]
+mod huge_page_blocks;
 mod instance;
+#[cfg(feature = "unstable-internals")]
+pub mod internals;
 mod memory;
+#[cfg(feature = "mmap")]
+mod mmap_blocks;
+#[cfg(all(feature = "alloc", feature = "zeroize"))]
+mod owned;
 mod params;
+mod phc_writer;
+#[cfg(feature = "std")]
+mod policy;
+#[cfg(feature = "alloc")]
+mod resume;
+#[cfg(feature = "alloc")]
+mod reusable;
+#[cfg(feature = "secure-alloc")]
+mod secure_blocks;
+mod self_test;
+#[cfg(feature = "stats")]
+mod stats;
+mod variable_hash;
 mod version;
+#[cfg(all(feature = "alloc", feature = "zeroize"))]
+mod zeroizing_blocks;
 
 pub use crate::{
+    access_pattern::ReferenceBlocks,
     algorithm::Algorithm,
     block::Block,
+    builder::Argon2Builder,
     error::{Error, Result},
-    params::{Params, ParamsBuilder},
+    memory::SYNC_POINTS,
+    params::{block_count, Params, ParamsBuilder, ParamsSummary, SecurityLevel},
+    self_test::self_test,
+    variable_hash::blake2b_long,
     version::Version,
 };
 
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use crate::policy::{global_max_memory, set_global_max_memory};
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub use crate::{resume::ResumeState, reusable::Argon2WithMemory};
+
+#[cfg(feature = "hugepages")]
+pub use crate::huge_page_blocks::HugePageBlocks;
+
+#[cfg(feature = "mmap")]
+pub use crate::mmap_blocks::MmapBlocks;
+
+#[cfg(feature = "stats")]
+pub use crate::stats::AccessStats;
+
+#[cfg(feature = "secure-alloc")]
+pub use crate::secure_blocks::SecureBlocks;
+
+#[cfg(all(feature = "alloc", feature = "zeroize"))]
+pub use crate::{owned::Argon2Owned, zeroizing_blocks::ZeroizingBlocks};
+
+#[cfg(all(feature = "std", feature = "zeroize"))]
+pub use crate::arena_pool::{ArenaGuard, ArenaPool};
+
 #[cfg(feature = "password-hash")]
 #[cfg_attr(docsrs, doc(cfg(feature = "password-hash")))]
 pub use {
@@ -88,11 +159,13 @@ pub use {
     password_hash::{self, PasswordHash, PasswordHasher, PasswordVerifier},
 };
 
-use crate::{
-    instance::Instance,
-    memory::{Memory, SYNC_POINTS},
-};
-use blake2::{digest::Output, Blake2b512, Digest};
+use crate::{instance::Instance, memory::Memory, variable_hash::blake2b_long_chunks};
+use blake2::{Blake2b512, Digest};
+use core::{cmp::Ordering, fmt, sync::atomic::AtomicBool};
+use subtle::{Choice, ConstantTimeEq};
+
+#[cfg(all(feature = "std", feature = "zeroize"))]
+use zeroize::Zeroize;
 
 #[cfg(all(feature = "alloc", feature = "password-hash"))]
 use password_hash::{Decimal, Ident, ParamsString, Salt};
@@ -109,6 +182,55 @@ pub const MAX_SALT_LEN: usize = 0xFFFFFFFF;
 /// Maximum secret key length in bytes.
 pub const MAX_SECRET_LEN: usize = 0xFFFFFFFF;
 
+// Lengths are hashed as `u32`s, so inputs which pass the length checks must
+// never be truncated
+const _: () = assert!(
+    MAX_PWD_LEN <= u32::MAX as usize
+        && MAX_SALT_LEN <= u32::MAX as usize
+        && MAX_SECRET_LEN <= u32::MAX as usize
+        && Params::MAX_OUTPUT_LEN <= u32::MAX as usize
+        && Params::MAX_DATA_LEN <= u32::MAX as usize
+);
+
+/// Encode raw salt bytes as a [`SaltString`][`password_hash::SaltString`]
+/// for use with [`PasswordHasher::hash_password`].
+///
+/// This allows generating salts without an RNG implementing the `rand_core`
+/// traits, e.g. from a hardware entropy source on embedded targets. The
+/// bytes must come from a cryptographically secure random number generator.
+///
+/// Returns [`Error::SaltTooShort`] if there are fewer than [`MIN_SALT_LEN`]
+/// bytes, and [`Error::SaltTooLong`] if they don't fit in a PHC salt string
+/// of at most [`Salt::MAX_LENGTH`][`password_hash::Salt::MAX_LENGTH`] B64
+/// characters, i.e. 48 bytes.
+#[cfg(feature = "password-hash")]
+#[cfg_attr(docsrs, doc(cfg(feature = "password-hash")))]
+pub fn salt_from_bytes(bytes: &[u8]) -> Result<password_hash::SaltString> {
+    if bytes.len() < MIN_SALT_LEN {
+        return Err(Error::SaltTooShort);
+    }
+
+    password_hash::SaltString::b64_encode(bytes).map_err(|_| Error::SaltTooLong)
+}
+
+/// Get the [`Algorithm`] of a PHC string hash from its leading `$argon2x$`
+/// identifier, without parsing the rest of it.
+///
+/// This allows checking cheaply whether a stored hash is an Argon2 hash, e.g.
+/// to dispatch to the right crate when verifying hashes of multiple
+/// algorithms. Returns `None` if the identifier isn't one of
+/// [`ARGON2D_IDENT`], [`ARGON2I_IDENT`] or [`ARGON2ID_IDENT`]. The rest of
+/// the hash may still be invalid.
+#[cfg(feature = "password-hash")]
+#[cfg_attr(docsrs, doc(cfg(feature = "password-hash")))]
+pub fn peek_algorithm(hash: &str) -> Option<Algorithm> {
+    let (ident, _) = hash.strip_prefix('$')?.split_once('$')?;
+
+    Algorithm::all()
+        .into_iter()
+        .find(|algorithm| algorithm.ident().as_str() == ident)
+}
+
 /// Argon2 context.
 ///
 /// This is the primary type of this crate's API, and contains the following:
@@ -117,6 +239,7 @@ pub const MAX_SECRET_LEN: usize = 0xFFFFFFFF;
 /// - Argon2 [`Version`] to be used
 /// - Default set of [`Params`] to be used
 /// - (Optional) Secret key a.k.a. "pepper" to be used
+/// - (Optional) Maximum number of threads to be used
 #[derive(Clone)]
 pub struct Argon2<'key> {
     /// Algorithm to use
@@ -130,6 +253,9 @@ pub struct Argon2<'key> {
 
     /// Key array
     secret: Option<&'key [u8]>,
+
+    /// Maximum number of threads
+    max_threads: usize,
 }
 
 impl Default for Argon2<'_> {
@@ -138,6 +264,27 @@ impl Default for Argon2<'_> {
     }
 }
 
+impl fmt::Debug for Argon2<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Placeholder printed instead of the secret key.
+        struct Redacted;
+
+        impl fmt::Debug for Redacted {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("<redacted>")
+            }
+        }
+
+        f.debug_struct("Argon2")
+            .field("algorithm", &self.algorithm)
+            .field("version", &self.version)
+            .field("params", &self.params)
+            .field("secret", &self.secret.map(|_| Redacted))
+            .field("max_threads", &self.max_threads)
+            .finish()
+    }
+}
+
 impl<'key> Argon2<'key> {
     /// Create a new Argon2 context.
     pub fn new(algorithm: Algorithm, version: Version, params: Params) -> Self {
@@ -146,9 +293,20 @@ impl<'key> Argon2<'key> {
             version,
             params,
             secret: None,
+            max_threads: usize::MAX,
         }
     }
 
+    /// Create a new Argon2 context with the default [`Algorithm`]
+    /// (Argon2id) and the given [`Version`].
+    ///
+    /// This is like [`Argon2::from`] with [`Params`], which uses the default
+    /// version, e.g. to verify hashes of an older system which used
+    /// [`Version::V0x10`].
+    pub fn from_params_and_version(params: Params, version: Version) -> Self {
+        Self::new(Algorithm::default(), version, params)
+    }
+
     /// Create a new Argon2 context.
     pub fn new_with_secret(
         secret: &'key [u8],
@@ -165,17 +323,124 @@ impl<'key> Argon2<'key> {
             version,
             params,
             secret: Some(secret),
+            max_threads: usize::MAX,
         })
     }
 
+    /// Limit the number of threads used to fill memory.
+    ///
+    /// By default one thread is used per lane, i.e. the degree of
+    /// parallelism (`p_cost`) determines the number of threads. With a lower
+    /// limit, all lanes are still computed but spread across at most
+    /// `max_threads` threads. The output doesn't depend on this setting.
+    ///
+    /// This only has an effect when the `parallel` or `parallel-std`
+    /// feature is enabled; otherwise a single thread is always used.
+    pub fn with_max_threads(mut self, max_threads: usize) -> Result<Self> {
+        if max_threads == 0 {
+            return Err(Error::ThreadsTooFew);
+        }
+
+        self.max_threads = max_threads;
+        Ok(self)
+    }
+
+    /// Enable or disable filling lanes on multiple threads.
+    ///
+    /// When disabled, the lanes are filled one after another on the calling
+    /// thread, as without the `parallel` and `parallel-std` features, e.g.
+    /// to avoid scheduling variability when profiling or debugging. This is
+    /// the same as [`Argon2::with_max_threads`] with a limit of one thread
+    /// when disabled, and removes the limit when enabled. The output doesn't
+    /// depend on this setting. It is enabled by default.
+    pub fn with_parallelism(mut self, enabled: bool) -> Self {
+        self.max_threads = if enabled { usize::MAX } else { 1 };
+        self
+    }
+
     /// Hash a password and associated parameters into the provided output buffer.
     #[cfg(feature = "alloc")]
     #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
     pub fn hash_password_into(&self, pwd: &[u8], salt: &[u8], out: &mut [u8]) -> Result<()> {
+        self.params.checked_memory_bytes()?;
+
+        #[cfg(feature = "std")]
+        policy::check_global_max_memory(self.memory_bytes())?;
+
+        // Zero-initializing the blocks is cheap: the allocation is typically
+        // optimized into `calloc`, which gets zeroed pages from the OS. The
+        // dominant setup cost is faulting in the pages on first write, which
+        // leaving the memory uninitialized wouldn't avoid; reusing memory
+        // (see `Argon2WithMemory`) does.
         let mut blocks = vec![Block::default(); self.params.block_count()];
         self.hash_password_into_with_memory(pwd, salt, out, &mut blocks)
     }
 
+    /// Derive a key from a password (or passphrase) and salt, e.g. a
+    /// 32-byte key for a symmetric cipher, into the provided output buffer.
+    ///
+    /// This is the same computation as [`Argon2::hash_password_into`],
+    /// exposed under a name suited to using Argon2 as a key derivation
+    /// function. The output may be any length between
+    /// [`Params::MIN_OUTPUT_LEN`] and [`Params::MAX_OUTPUT_LEN`] (or exactly
+    /// [`Params::output_len`] if set), and the salt must be at least
+    /// [`MIN_SALT_LEN`] bytes long (see [`Argon2::derive_key_with_short_salt`]
+    /// otherwise).
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    pub fn derive_key(&self, password: &[u8], salt: &[u8], output: &mut [u8]) -> Result<()> {
+        self.hash_password_into(password, salt, output)
+    }
+
+    /// Derive a key like [`Argon2::derive_key`], but accepting salts shorter
+    /// than [`MIN_SALT_LEN`], including an empty salt, and using the
+    /// provided memory blocks.
+    ///
+    /// This is only for key derivation protocols which don't use a salt
+    /// because uniqueness is provided by other means. It must not be used
+    /// for password storage: without a unique salt, identical passwords have
+    /// identical hashes and can be attacked with precomputed tables.
+    pub fn derive_key_with_short_salt(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+        output: &mut [u8],
+        memory_blocks: impl AsMut<[Block]>,
+    ) -> Result<()> {
+        self.validate_lengths(password.len(), salt, output.len(), 0)?;
+
+        // Hashing all inputs
+        let initial_hash = self.initial_hash(password, salt, output.len());
+
+        self.fill_and_finalize(initial_hash, memory_blocks, None, |blockhash| {
+            blake2b_long(&[blockhash], output)
+        })
+    }
+
+    /// Hash a password and associated parameters into the provided output
+    /// buffer, filling lanes in parallel on the given rayon thread pool
+    /// rather than the global one.
+    ///
+    /// This can be used to bound the number of threads used for hashing.
+    /// Otherwise it behaves like [`Argon2::hash_password_into`].
+    ///
+    /// Rayon thread pools aren't poisoned by panics: a panic in another job
+    /// is propagated to whoever started that job, and the pool remains
+    /// usable for hashing. Filling the lanes doesn't panic for inputs which
+    /// pass validation, so hashing on a pool returns the same errors as
+    /// hashing without one.
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+    pub fn hash_password_into_with_pool(
+        &self,
+        pwd: &[u8],
+        salt: &[u8],
+        out: &mut [u8],
+        pool: &rayon::ThreadPool,
+    ) -> Result<()> {
+        pool.install(|| self.hash_password_into(pwd, salt, out))
+    }
+
     /// Hash a password and associated parameters into the provided output buffer.
     ///
     /// This method takes an explicit `memory_blocks` parameter which allows
@@ -185,28 +450,379 @@ impl<'key> Argon2<'key> {
     ///   to have it allocated for them.
     /// - `no_std` users on "heapless" targets can use an array of the [`Block`] type
     ///   to stack allocate this buffer.
+    ///
+    /// When the `zeroize` feature is enabled, the blocks used for hashing are
+    /// zeroized before returning, whether hashing succeeded or not.
     pub fn hash_password_into_with_memory(
+        &self,
+        pwd: &[u8],
+        salt: &[u8],
+        out: &mut [u8],
+        memory_blocks: impl AsMut<[Block]>,
+    ) -> Result<()> {
+        self.hash_password_into_inner(pwd, salt, out, memory_blocks, None)
+    }
+
+    /// Hash a password and associated parameters into the provided output
+    /// buffer like [`Argon2::hash_password_into_with_memory`], requiring
+    /// exactly [`Params::block_count`] memory blocks.
+    ///
+    /// [`Argon2::hash_password_into_with_memory`] only uses the first
+    /// [`Params::block_count`] blocks of a larger buffer. This returns
+    /// [`Error::MemoryTooMuch`] instead, which helps to catch a buffer sized
+    /// for other parameters being reused by mistake.
+    pub fn hash_password_into_with_memory_exact(
         &self,
         pwd: &[u8],
         salt: &[u8],
         out: &mut [u8],
         mut memory_blocks: impl AsMut<[Block]>,
     ) -> Result<()> {
-        // Validate output length
-        if out.len() < self.params.output_len().unwrap_or(Params::MIN_OUTPUT_LEN) {
+        match memory_blocks.as_mut().len().cmp(&self.params.block_count()) {
+            Ordering::Less => Err(Error::MemoryTooLittle),
+            Ordering::Greater => Err(Error::MemoryTooMuch),
+            Ordering::Equal => self.hash_password_into_with_memory(pwd, salt, out, memory_blocks),
+        }
+    }
+
+    /// Verify a password against a previously computed raw hash, using the
+    /// provided memory blocks for hashing.
+    ///
+    /// The hash of `pwd` and `salt` is recomputed and compared with
+    /// `expected` in constant time, returning [`Error::PwdInvalid`] if they
+    /// differ. The whole hash is always compared, so the time taken doesn't
+    /// depend on where the first difference is. `expected` must have a
+    /// valid output length, as with [`Argon2::hash_password_into_with_memory`].
+    ///
+    /// This is the counterpart of [`PasswordVerifier::verify_password`] for
+    /// raw hashes, and works without the `alloc` and `password-hash` features.
+    pub fn verify_password_into(
+        &self,
+        pwd: &[u8],
+        salt: &[u8],
+        expected: &[u8],
+        memory_blocks: impl AsMut<[Block]>,
+    ) -> Result<()> {
+        self.validate_inputs(pwd.len(), salt, expected.len())?;
+
+        // Hashing all inputs
+        let initial_hash = self.initial_hash(pwd, salt, expected.len());
+
+        let mut matches = Choice::from(1);
+        self.fill_and_finalize(initial_hash, memory_blocks, None, |blockhash| {
+            let mut pos = 0;
+
+            blake2b_long_chunks(&[blockhash], expected.len(), |chunk| {
+                matches &= chunk.ct_eq(&expected[pos..][..chunk.len()]);
+                pos += chunk.len();
+            })
+        })?;
+
+        if matches.into() {
+            Ok(())
+        } else {
+            Err(Error::PwdInvalid)
+        }
+    }
+
+    /// Verify a password against a PHC string hash like
+    /// [`PasswordVerifier::verify_password`], using the provided memory
+    /// blocks for hashing instead of allocating them.
+    ///
+    /// The algorithm, version and parameters are read from `hash`, and the
+    /// secret of this context is used. `memory_blocks` must contain at least
+    /// [`Params::block_count`] blocks for the hash's parameters, otherwise
+    /// an error converted from [`Error::MemoryTooLittle`] is returned. The
+    /// hashes are compared in constant time, as with
+    /// [`Argon2::verify_password_into`].
+    #[cfg(feature = "password-hash")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "password-hash")))]
+    pub fn verify_password_with_memory(
+        &self,
+        pwd: &[u8],
+        hash: &PasswordHash<'_>,
+        memory_blocks: impl AsMut<[Block]>,
+    ) -> password_hash::Result<()> {
+        let (salt, expected) = match (&hash.salt, &hash.hash) {
+            (Some(salt), Some(expected)) => (salt, expected),
+            _ => return Err(password_hash::Error::Password),
+        };
+
+        let argon2 = Argon2 {
+            algorithm: Algorithm::try_from(hash.algorithm)?,
+            version: hash
+                .version
+                .map(Version::try_from)
+                .transpose()?
+                .unwrap_or_default(),
+            params: Params::try_from(hash)?,
+            secret: self.secret,
+            max_threads: self.max_threads,
+        };
+
+        let mut salt_arr = [0u8; password_hash::Salt::MAX_LENGTH * 3 / 4];
+        let salt_bytes = salt.b64_decode(&mut salt_arr)?;
+
+        argon2.verify_password_into(pwd, salt_bytes, expected.as_bytes(), memory_blocks)?;
+        Ok(())
+    }
+
+    /// Verify a password against a PHC string hash like
+    /// [`PasswordVerifier::verify_password`], succeeding if it matches with
+    /// any of the given secret keys a.k.a. "peppers".
+    ///
+    /// This is intended for rotating the secret: while hashes computed with
+    /// either the old or the new secret are stored, both can be passed
+    /// without recording which secret each hash uses. The secret of this
+    /// context is ignored.
+    ///
+    /// The hash is always recomputed with every secret, and the results are
+    /// combined in constant time, so the time taken doesn't depend on which
+    /// secret matched. It does grow with the number of secrets.
+    #[cfg(all(feature = "alloc", feature = "password-hash"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "password-hash")))]
+    pub fn verify_password_with_secrets(
+        &self,
+        pwd: &[u8],
+        hash: &PasswordHash<'_>,
+        secrets: &[&[u8]],
+    ) -> password_hash::Result<()> {
+        if secrets.iter().any(|secret| MAX_SECRET_LEN < secret.len()) {
+            return Err(Error::SecretTooLong.into());
+        }
+
+        let params = Params::try_from(hash)?;
+        params.checked_memory_bytes()?;
+
+        #[cfg(feature = "std")]
+        policy::check_global_max_memory(params.memory_bytes())?;
+
+        let mut memory = vec![Block::default(); params.block_count()];
+        let mut matches = Choice::from(0);
+
+        for secret in secrets {
+            let argon2 = Argon2 {
+                algorithm: self.algorithm,
+                version: self.version,
+                params: self.params.clone(),
+                secret: Some(secret),
+                max_threads: self.max_threads,
+            };
+
+            match argon2.verify_password_with_memory(pwd, hash, &mut memory) {
+                Ok(()) => matches |= Choice::from(1),
+                Err(password_hash::Error::Password) => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        if matches.into() {
+            Ok(())
+        } else {
+            Err(password_hash::Error::Password)
+        }
+    }
+
+    /// Hash a password and associated parameters into the provided output
+    /// buffer, using the given secret key a.k.a. "pepper".
+    ///
+    /// `secret` replaces the secret this context was created with (if any)
+    /// for this call only, which allows using a different secret for every
+    /// call without creating a new context. Otherwise this behaves like
+    /// [`Argon2::hash_password_into_with_memory`].
+    pub fn hash_password_into_with_secret(
+        &self,
+        pwd: &[u8],
+        salt: &[u8],
+        secret: &[u8],
+        out: &mut [u8],
+        memory_blocks: impl AsMut<[Block]>,
+    ) -> Result<()> {
+        if MAX_SECRET_LEN < secret.len() {
+            return Err(Error::SecretTooLong);
+        }
+
+        Argon2 {
+            params: self.params.clone(),
+            secret: Some(secret),
+            ..*self
+        }
+        .hash_password_into_with_memory(pwd, salt, out, memory_blocks)
+    }
+
+    /// Hash several passwords, reusing the same memory for each of them.
+    ///
+    /// Each `(password, salt)` pair in `inputs` is hashed into the output
+    /// buffer at the same index in `outputs`, as if by calling
+    /// [`Argon2::hash_password_into_with_memory`] with `memory_blocks`.
+    /// This avoids allocating memory for every hash when hashing in bulk.
+    ///
+    /// Each input is validated independently. Hashing stops at the first
+    /// error, in which case the outputs of the following inputs are left
+    /// untouched.
+    ///
+    /// When the `zeroize` feature is enabled, `memory_blocks` is zeroized
+    /// after each hash.
+    ///
+    /// # Panics
+    ///
+    /// If `inputs` and `outputs` have different lengths.
+    pub fn hash_many_into(
+        &self,
+        inputs: &[(&[u8], &[u8])],
+        outputs: &mut [&mut [u8]],
+        memory_blocks: &mut [Block],
+    ) -> Result<()> {
+        assert_eq!(
+            inputs.len(),
+            outputs.len(),
+            "inputs and outputs must have the same length"
+        );
+
+        for (&(pwd, salt), out) in inputs.iter().zip(outputs.iter_mut()) {
+            self.hash_password_into_with_memory(pwd, salt, out, &mut *memory_blocks)?;
+        }
+
+        Ok(())
+    }
+
+    /// Hash a password and associated parameters into the provided output
+    /// buffer, aborting if `cancel` is set while hashing is in progress.
+    ///
+    /// This behaves like [`Argon2::hash_password_into_with_memory`], except
+    /// that `cancel` is checked at every synchronization point (four times
+    /// per pass). Once it is set, hashing stops and [`Error::Cancelled`] is
+    /// returned. This can be used to enforce a deadline on expensive hashes,
+    /// e.g. by setting the flag from another thread.
+    ///
+    /// As with [`Argon2::hash_password_into_with_memory`], the blocks used
+    /// for hashing are zeroized when the `zeroize` feature is enabled,
+    /// including if hashing is cancelled.
+    pub fn hash_password_into_with_memory_cancellable(
+        &self,
+        pwd: &[u8],
+        salt: &[u8],
+        out: &mut [u8],
+        memory_blocks: impl AsMut<[Block]>,
+        cancel: &AtomicBool,
+    ) -> Result<()> {
+        self.hash_password_into_inner(pwd, salt, out, memory_blocks, Some(cancel))
+    }
+
+    fn hash_password_into_inner(
+        &self,
+        pwd: &[u8],
+        salt: &[u8],
+        out: &mut [u8],
+        memory_blocks: impl AsMut<[Block]>,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<()> {
+        self.validate_inputs(pwd.len(), salt, out.len())?;
+
+        // Hashing all inputs
+        let initial_hash = self.initial_hash(pwd, salt, out.len());
+
+        self.fill_and_finalize(initial_hash, memory_blocks, cancel, |blockhash| {
+            blake2b_long(&[blockhash], out)
+        })
+    }
+
+    /// Hash a password read from `pwd` and associated parameters into the
+    /// provided output buffer.
+    ///
+    /// Exactly `pwd_len` bytes are read from `pwd` and fed incrementally
+    /// into the initial BLAKE2b hash, so that large passwords (e.g. key
+    /// files) don't need to be buffered in memory. The length must be known
+    /// up front as it is hashed before the password itself. Returns
+    /// [`Error::PwdReadFailed`] if reading fails or ends early.
+    ///
+    /// The output is the same as [`Argon2::hash_password_into_with_memory`]
+    /// with the same password as a slice.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn hash_reader_into(
+        &self,
+        mut pwd: impl std::io::Read,
+        pwd_len: u32,
+        salt: &[u8],
+        out: &mut [u8],
+        memory_blocks: impl AsMut<[Block]>,
+    ) -> Result<()> {
+        self.validate_inputs(pwd_len as usize, salt, out.len())?;
+
+        // Hashing all inputs
+        let mut read_result = Ok(());
+        let initial_hash = self.initial_hash_with(
+            pwd_len,
+            |digest| {
+                let mut buf = [0u8; 1024];
+                let mut remaining = pwd_len as usize;
+
+                while remaining > 0 {
+                    let n = remaining.min(buf.len());
+
+                    if pwd.read_exact(&mut buf[..n]).is_err() {
+                        read_result = Err(Error::PwdReadFailed);
+                        break;
+                    }
+
+                    digest.update(&buf[..n]);
+                    remaining -= n;
+                }
+
+                #[cfg(feature = "zeroize")]
+                buf.zeroize();
+            },
+            salt,
+            out.len(),
+        );
+        read_result?;
+
+        self.fill_and_finalize(initial_hash, memory_blocks, None, |blockhash| {
+            blake2b_long(&[blockhash], out)
+        })
+    }
+
+    /// Validate the lengths of the inputs to a hash.
+    fn validate_inputs(&self, pwd_len: usize, salt: &[u8], out_len: usize) -> Result<()> {
+        self.validate_lengths(pwd_len, salt, out_len, MIN_SALT_LEN)
+    }
+
+    /// Validate the input lengths, allowing salts of at least `min_salt_len`
+    /// bytes.
+    fn validate_lengths(
+        &self,
+        pwd_len: usize,
+        salt: &[u8],
+        out_len: usize,
+        min_salt_len: usize,
+    ) -> Result<()> {
+        // Validate output length, rejecting an empty output regardless of
+        // the configured length
+        if out_len == 0 {
             return Err(Error::OutputTooShort);
         }
 
-        if out.len() > self.params.output_len().unwrap_or(Params::MAX_OUTPUT_LEN) {
-            return Err(Error::OutputTooLong);
+        match self.params.output_len() {
+            Some(expected) if out_len != expected => {
+                return Err(Error::OutputLenMismatch {
+                    expected,
+                    actual: out_len,
+                });
+            }
+            Some(_) => (),
+            None if out_len < Params::MIN_OUTPUT_LEN => return Err(Error::OutputTooShort),
+            None if out_len > Params::MAX_OUTPUT_LEN => return Err(Error::OutputTooLong),
+            None => (),
         }
 
-        if pwd.len() > MAX_PWD_LEN {
+        if pwd_len > MAX_PWD_LEN {
             return Err(Error::PwdTooLong);
         }
 
         // Validate salt (required param)
-        if salt.len() < MIN_SALT_LEN {
+        if salt.len() < min_salt_len {
             return Err(Error::SaltTooShort);
         }
 
@@ -214,9 +830,18 @@ impl<'key> Argon2<'key> {
             return Err(Error::SaltTooLong);
         }
 
-        // Hashing all inputs
-        let initial_hash = self.initial_hash(pwd, salt, out);
+        Ok(())
+    }
 
+    /// Fill the memory blocks starting from the initial hash and compute the
+    /// tag from the final block with `make_tag`.
+    fn fill_and_finalize(
+        &self,
+        initial_hash: [u8; 64],
+        mut memory_blocks: impl AsMut<[Block]>,
+        cancel: Option<&AtomicBool>,
+        make_tag: impl FnOnce(&[u8]) -> Result<()>,
+    ) -> Result<()> {
         let segment_length = self.params.segment_length();
         let block_count = self.params.block_count();
         let memory_blocks = memory_blocks
@@ -225,57 +850,235 @@ impl<'key> Argon2<'key> {
             .ok_or(Error::MemoryTooLittle)?;
 
         let memory = Memory::new(memory_blocks, segment_length);
-        Instance::hash(self, self.algorithm, initial_hash, memory, out)
+        Instance::hash(self, self.algorithm, initial_hash, memory, cancel, make_tag)
     }
 
-    /// Get default configured [`Params`].
-    pub fn params(&self) -> &Params {
-        &self.params
+    /// Verify a password against a PHC string which is missing its leading
+    /// `$<algorithm>` identifier, i.e. `v=19$m=...,t=...,p=...$salt$hash`.
+    ///
+    /// The [`Algorithm`] is supplied by the caller rather than read from the
+    /// string, and the remainder is parsed as a PHC string.
+    ///
+    /// This is only intended for migrating legacy records which were stored
+    /// without an algorithm identifier. Complete PHC strings should be
+    /// verified using [`PasswordVerifier::verify_password`].
+    #[cfg(all(feature = "alloc", feature = "password-hash"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "password-hash")))]
+    pub fn verify_str_with_algorithm(
+        &self,
+        algorithm: Algorithm,
+        pwd: &[u8],
+        partial_hash: &str,
+    ) -> password_hash::Result<()> {
+        let partial_hash = partial_hash.strip_prefix('$').unwrap_or(partial_hash);
+        let hash_string = format!("${}${}", algorithm, partial_hash);
+        self.verify_password(pwd, &PasswordHash::new(&hash_string)?)
     }
 
-    /// Hashes all the inputs into `blockhash[PREHASH_DIGEST_LEN]`.
-    pub(crate) fn initial_hash(&self, pwd: &[u8], salt: &[u8], out: &[u8]) -> Output<Blake2b512> {
-        let mut digest = Blake2b512::new();
-        digest.update(&self.params.lanes().to_le_bytes());
-        digest.update(&(out.len() as u32).to_le_bytes());
-        digest.update(&self.params.m_cost().to_le_bytes());
-        digest.update(&self.params.t_cost().to_le_bytes());
-        digest.update(&self.version.to_le_bytes());
-        digest.update(&self.algorithm.to_le_bytes());
-        digest.update(&(pwd.len() as u32).to_le_bytes());
-        digest.update(pwd);
-        digest.update(&(salt.len() as u32).to_le_bytes());
-        digest.update(salt);
+    /// Verify a password against a PHC string hash like
+    /// [`PasswordVerifier::verify_password`], but only if the hash was
+    /// computed with this context's [`Algorithm`] and [`Version`].
+    ///
+    /// [`PasswordVerifier::verify_password`] always uses the algorithm and
+    /// version of the hash, which can mask configuration errors such as a
+    /// migration to a new algorithm that didn't fully happen. This returns
+    /// [`Error::AlgorithmInvalid`] (as a [`password_hash::Error`]) if
+    /// either differs instead. The hash's parameters are still used.
+    #[cfg(all(feature = "alloc", feature = "password-hash"))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "password-hash")))]
+    pub fn verify_password_strict(
+        &self,
+        pwd: &[u8],
+        hash: &PasswordHash<'_>,
+    ) -> password_hash::Result<()> {
+        let algorithm = Algorithm::try_from(hash.algorithm)?;
+        let version = hash
+            .version
+            .map(Version::try_from)
+            .transpose()?
+            .unwrap_or_default();
 
-        if let Some(secret) = &self.secret {
-            digest.update(&(secret.len() as u32).to_le_bytes());
-            digest.update(secret);
-        } else {
-            digest.update(0u32.to_le_bytes());
+        if algorithm != self.algorithm || version != self.version {
+            return Err(Error::AlgorithmInvalid.into());
         }
 
-        digest.update(&(self.params.data().len() as u32).to_le_bytes());
-        digest.update(self.params.data());
-        digest.finalize()
+        self.verify_password(pwd, hash)
     }
-}
 
-#[cfg(all(feature = "alloc", feature = "password-hash"))]
-#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
-#[cfg_attr(docsrs, doc(cfg(feature = "password-hash")))]
-impl PasswordHasher for Argon2<'_> {
-    type Params = Params;
+    /// Get default configured [`Params`].
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
 
-    fn hash_password<'a, S>(
-        &self,
-        password: &[u8],
-        salt: &'a S,
-    ) -> password_hash::Result<PasswordHash<'a>>
-    where
+    /// Get the amount of memory in bytes required to hash a password with
+    /// this context's [`Params`].
+    ///
+    /// See [`Params::memory_bytes`].
+    pub fn memory_bytes(&self) -> usize {
+        self.params.memory_bytes()
+    }
+
+    /// Get the number of memory blocks required to hash a password with
+    /// this context's [`Params`], i.e. the minimum length of the
+    /// `memory_blocks` passed to [`Argon2::hash_password_into_with_memory`].
+    ///
+    /// ```
+    /// # fn main() -> argon2::Result<()> {
+    /// use argon2::{Argon2, Block};
+    ///
+    /// let argon2 = Argon2::default();
+    /// let mut memory = vec![Block::default(); argon2.required_blocks()];
+    ///
+    /// let mut out = [0u8; 32];
+    /// argon2.hash_password_into_with_memory(b"password", b"somesalt", &mut out, &mut memory)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// See [`Params::block_count`].
+    pub fn required_blocks(&self) -> usize {
+        self.params.block_count()
+    }
+
+    /// Is this configuration at least as strong as `other`?
+    ///
+    /// "Stronger" is a matter of policy rather than a purely numeric
+    /// comparison. This returns `true` only if all of the following hold:
+    ///
+    /// - the [`Algorithm`] is the same as `other`'s, or is
+    ///   [`Algorithm::Argon2id`], which is preferred over both Argon2i and
+    ///   Argon2d as it resists both side-channel and GPU cracking attacks.
+    ///   Argon2i and Argon2d are not considered comparable to each other.
+    /// - the [`Version`] is the same or newer.
+    /// - the memory cost (`m_cost`) and time cost (`t_cost`) are both equal
+    ///   or higher.
+    ///
+    /// The degree of parallelism (`p_cost`), output length, and secret key
+    /// are not taken into account.
+    pub fn is_at_least_as_strong_as(&self, other: &Argon2<'_>) -> bool {
+        let algorithm_ok =
+            self.algorithm == other.algorithm || self.algorithm == Algorithm::Argon2id;
+
+        algorithm_ok
+            && self.version >= other.version
+            && self.params.m_cost() >= other.params.m_cost()
+            && self.params.t_cost() >= other.params.t_cost()
+    }
+
+    /// Does a stored password hash need to be recomputed with this
+    /// configuration, e.g. after a successful login?
+    ///
+    /// Returns `true` if the hash's configuration is weaker than this one,
+    /// i.e. it isn't [at least as strong](Argon2::is_at_least_as_strong_as)
+    /// or has a lower degree of parallelism (`p_cost`), and `false` if it is
+    /// equal or stronger. Hashes whose algorithm, version or parameters
+    /// can't be parsed also need to be recomputed.
+    #[cfg(feature = "password-hash")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "password-hash")))]
+    pub fn needs_rehash(&self, hash: &PasswordHash<'_>) -> bool {
+        let algorithm = match Algorithm::try_from(hash.algorithm) {
+            Ok(algorithm) => algorithm,
+            Err(_) => return true,
+        };
+
+        let version = match hash.version.map(Version::try_from).transpose() {
+            Ok(version) => version.unwrap_or_default(),
+            Err(_) => return true,
+        };
+
+        let params = match Params::try_from(hash) {
+            Ok(params) => params,
+            Err(_) => return true,
+        };
+
+        let p_cost = params.p_cost();
+        let stored = Argon2::new(algorithm, version, params);
+
+        !stored.is_at_least_as_strong_as(self) || p_cost < self.params.p_cost()
+    }
+
+    /// Compute the initial hash `H0` of the given inputs for an output of
+    /// `out_len` bytes, from which the first memory blocks are derived.
+    ///
+    /// This is only intended for test vectors and for comparing intermediate
+    /// values with other implementations. `H0` is a single BLAKE2b hash of
+    /// the inputs, which is cheap to brute-force, so it must not be used as
+    /// a password hash or key itself.
+    ///
+    /// # Panics
+    ///
+    /// If the length of `pwd`, `salt` or the output exceeds `u32::MAX`, as
+    /// lengths are hashed as 32-bit integers.
+    pub fn initial_hash(&self, pwd: &[u8], salt: &[u8], out_len: usize) -> [u8; 64] {
+        assert!(pwd.len() <= MAX_PWD_LEN, "password is too long");
+        assert!(salt.len() <= MAX_SALT_LEN, "salt is too long");
+        assert!(out_len <= Params::MAX_OUTPUT_LEN, "output is too long");
+
+        self.initial_hash_with(pwd.len() as u32, |digest| digest.update(pwd), salt, out_len)
+    }
+
+    /// Hashes all the inputs into `blockhash[PREHASH_DIGEST_LEN]`, where
+    /// `update_pwd` feeds the `pwd_len` bytes of the password into the hash.
+    fn initial_hash_with(
+        &self,
+        pwd_len: u32,
+        update_pwd: impl FnOnce(&mut Blake2b512),
+        salt: &[u8],
+        out_len: usize,
+    ) -> [u8; 64] {
+        let mut digest = Blake2b512::new();
+        digest.update(&self.params.lanes().to_le_bytes());
+        digest.update(&(out_len as u32).to_le_bytes());
+        digest.update(&self.params.m_cost().to_le_bytes());
+        digest.update(&self.params.t_cost().to_le_bytes());
+        digest.update(&self.version.to_le_bytes());
+        digest.update(&self.algorithm.to_le_bytes());
+        digest.update(&pwd_len.to_le_bytes());
+        update_pwd(&mut digest);
+        digest.update(&(salt.len() as u32).to_le_bytes());
+        digest.update(salt);
+
+        if let Some(secret) = &self.secret {
+            digest.update(&(secret.len() as u32).to_le_bytes());
+            digest.update(secret);
+        } else {
+            digest.update(0u32.to_le_bytes());
+        }
+
+        digest.update(&(self.params.data().len() as u32).to_le_bytes());
+        digest.update(self.params.data());
+        digest.finalize().into()
+    }
+}
+
+/// Hashing of PHC strings.
+///
+/// [`PasswordVerifier`] is implemented in terms of this trait, comparing the
+/// recomputed hash with the expected one through [`password_hash::Output`]'s
+/// `PartialEq` impl, which is constant-time. Raw hashes can be verified in
+/// constant time with [`Argon2::verify_password_into`].
+#[cfg(all(feature = "alloc", feature = "password-hash"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "password-hash")))]
+impl PasswordHasher for Argon2<'_> {
+    type Params = Params;
+
+    fn hash_password<'a, S>(
+        &self,
+        password: &[u8],
+        salt: &'a S,
+    ) -> password_hash::Result<PasswordHash<'a>>
+    where
         S: AsRef<str> + ?Sized,
     {
         let salt = Salt::try_from(salt.as_ref())?;
-        let mut salt_arr = [0u8; 64];
+
+        // `Salt` is at most `Salt::MAX_LENGTH` B64 characters, which decode
+        // to at most 3/4 as many bytes. Longer salts can only be used with
+        // the raw hashing APIs, e.g. `hash_password_into`.
+        let mut salt_arr = [0u8; Salt::MAX_LENGTH * 3 / 4];
         let salt_bytes = salt.b64_decode(&mut salt_arr)?;
         let output_len = self
             .params
@@ -320,6 +1123,7 @@ impl PasswordHasher for Argon2<'_> {
             algorithm,
             version,
             params,
+            max_threads: self.max_threads,
         }
         .hash_password(password, salt.as_str())
     }
@@ -339,7 +1143,12 @@ impl<'key> From<&Params> for Argon2<'key> {
 
 #[cfg(all(test, feature = "alloc", feature = "password-hash"))]
 mod tests {
-    use crate::{Algorithm, Argon2, Params, PasswordHasher, Salt, Version};
+    use crate::{
+        Algorithm, Argon2, Argon2WithMemory, Block, Error, Params, PasswordHash, PasswordHasher,
+        PasswordVerifier, Salt, Version,
+    };
+    use alloc::vec::Vec;
+    use core::sync::atomic::AtomicBool;
 
     /// Example password only: don't use this as a real password!!!
     const EXAMPLE_PASSWORD: &[u8] = b"hunter42";
@@ -390,4 +1199,990 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn strength_comparison() {
+        let weak_params = Params::new(4096, 2, 1, None).unwrap();
+        let strong_params = Params::new(8192, 3, 1, None).unwrap();
+
+        let argon2id = Argon2::new(Algorithm::Argon2id, Version::V0x13, strong_params.clone());
+        let argon2i = Argon2::new(Algorithm::Argon2i, Version::V0x13, strong_params.clone());
+        let argon2d = Argon2::new(Algorithm::Argon2d, Version::V0x13, strong_params.clone());
+
+        // Argon2id is preferred over the other variants, which are not comparable
+        assert!(argon2id.is_at_least_as_strong_as(&argon2i));
+        assert!(argon2id.is_at_least_as_strong_as(&argon2d));
+        assert!(!argon2i.is_at_least_as_strong_as(&argon2id));
+        assert!(!argon2i.is_at_least_as_strong_as(&argon2d));
+        assert!(!argon2d.is_at_least_as_strong_as(&argon2i));
+
+        // Equal configurations are at least as strong as each other
+        assert!(argon2id.is_at_least_as_strong_as(&argon2id.clone()));
+
+        let old_version = Argon2::new(Algorithm::Argon2id, Version::V0x10, strong_params);
+        assert!(argon2id.is_at_least_as_strong_as(&old_version));
+        assert!(!old_version.is_at_least_as_strong_as(&argon2id));
+
+        let weak = Argon2::new(Algorithm::Argon2id, Version::V0x13, weak_params);
+        assert!(argon2id.is_at_least_as_strong_as(&weak));
+        assert!(!weak.is_at_least_as_strong_as(&argon2id));
+    }
+
+    #[test]
+    fn hash_password_longest_salt() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        let salt_bytes = [0x5a; 48];
+        let salt = password_hash::SaltString::b64_encode(&salt_bytes).unwrap();
+        assert_eq!(salt.len(), Salt::MAX_LENGTH);
+
+        let hash = argon2.hash_password(EXAMPLE_PASSWORD, &salt).unwrap();
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(EXAMPLE_PASSWORD, &salt_bytes, &mut expected)
+            .unwrap();
+        assert_eq!(hash.hash.unwrap().as_bytes(), expected);
+
+        // Longer salts, e.g. 96 bytes, don't fit in a PHC string salt but
+        // can be used with the raw API
+        let long_salt = [0x5a; 96];
+        argon2
+            .hash_password_into(EXAMPLE_PASSWORD, &long_salt, &mut expected)
+            .unwrap();
+    }
+
+    #[test]
+    fn debug_redacts_secret() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new_with_secret(
+            b"very secret pepper",
+            Algorithm::Argon2id,
+            Version::V0x13,
+            params.clone(),
+        )
+        .unwrap();
+
+        let debug = format!("{:?}", argon2);
+        assert!(debug.contains("secret: Some(<redacted>)"), "{}", debug);
+        assert!(!debug.contains("118"), "{}", debug);
+
+        let debug = format!(
+            "{:?}",
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        );
+        assert!(debug.contains("algorithm: Argon2id"), "{}", debug);
+        assert!(debug.contains("secret: None"), "{}", debug);
+    }
+
+    #[test]
+    fn needs_rehash() {
+        let params = Params::new(4096, 2, 2, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        for (hash, expected) in [
+            (
+                "$argon2id$v=19$m=4096,t=2,p=2$c29tZXNhbHQ$AAECAwQFBgcICQoLDA0ODw",
+                false,
+            ),
+            (
+                "$argon2id$v=19$m=8192,t=3,p=4$c29tZXNhbHQ$AAECAwQFBgcICQoLDA0ODw",
+                false,
+            ),
+            (
+                "$argon2id$v=19$m=2048,t=2,p=2$c29tZXNhbHQ$AAECAwQFBgcICQoLDA0ODw",
+                true,
+            ),
+            (
+                "$argon2id$v=19$m=4096,t=1,p=2$c29tZXNhbHQ$AAECAwQFBgcICQoLDA0ODw",
+                true,
+            ),
+            (
+                "$argon2id$v=19$m=4096,t=2,p=1$c29tZXNhbHQ$AAECAwQFBgcICQoLDA0ODw",
+                true,
+            ),
+            (
+                "$argon2id$v=16$m=4096,t=2,p=2$c29tZXNhbHQ$AAECAwQFBgcICQoLDA0ODw",
+                true,
+            ),
+            (
+                "$argon2i$v=19$m=4096,t=2,p=2$c29tZXNhbHQ$AAECAwQFBgcICQoLDA0ODw",
+                true,
+            ),
+            (
+                "$argon2id$v=19$m=4096,p=2$c29tZXNhbHQ$AAECAwQFBgcICQoLDA0ODw",
+                true,
+            ),
+            (
+                "$scrypt$ln=16,r=8,p=1$c29tZXNhbHQ$AAECAwQFBgcICQoLDA0ODw",
+                true,
+            ),
+        ] {
+            let hash = PasswordHash::new(hash).unwrap();
+            assert_eq!(argon2.needs_rehash(&hash), expected, "{}", hash);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn hash_with_pool() {
+        let params = Params::new(64, 2, 4, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = EXAMPLE_SALT.as_bytes();
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(EXAMPLE_PASSWORD, salt, &mut expected)
+            .unwrap();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        let mut out = [0u8; 32];
+        argon2
+            .hash_password_into_with_pool(EXAMPLE_PASSWORD, salt, &mut out, &pool)
+            .unwrap();
+        assert_eq!(out, expected);
+    }
+
+    /// A panic in an unrelated job doesn't prevent hashing on the same pool.
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn hash_with_pool_after_panic() {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        use std::panic;
+
+        let params = Params::new(64, 2, 4, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = EXAMPLE_SALT.as_bytes();
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(EXAMPLE_PASSWORD, salt, &mut expected)
+            .unwrap();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            pool.install(|| {
+                (0..4).into_par_iter().for_each(|i| {
+                    if i == 3 {
+                        panic!("unrelated job panicked");
+                    }
+                })
+            })
+        }));
+        assert!(result.is_err());
+
+        let mut out = [0u8; 32];
+        argon2
+            .hash_password_into_with_pool(EXAMPLE_PASSWORD, salt, &mut out, &pool)
+            .unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn max_threads() {
+        let params = Params::new(64, 2, 4, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = EXAMPLE_SALT.as_bytes();
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(EXAMPLE_PASSWORD, salt, &mut expected)
+            .unwrap();
+
+        for max_threads in 1..=5 {
+            let mut out = [0u8; 32];
+            argon2
+                .clone()
+                .with_max_threads(max_threads)
+                .unwrap()
+                .hash_password_into(EXAMPLE_PASSWORD, salt, &mut out)
+                .unwrap();
+            assert_eq!(out, expected);
+        }
+
+        assert!(matches!(
+            argon2.with_max_threads(0),
+            Err(Error::ThreadsTooFew)
+        ));
+    }
+
+    #[test]
+    fn with_parallelism() {
+        let params = Params::new(64, 2, 4, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = EXAMPLE_SALT.as_bytes();
+
+        let sequential = argon2.clone().with_parallelism(false);
+        assert_eq!(sequential.max_threads, 1);
+        assert_eq!(
+            sequential.clone().with_parallelism(true).max_threads,
+            usize::MAX
+        );
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(EXAMPLE_PASSWORD, salt, &mut expected)
+            .unwrap();
+        let mut out = [0u8; 32];
+        sequential
+            .hash_password_into(EXAMPLE_PASSWORD, salt, &mut out)
+            .unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn hash_many() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let inputs: [(&[u8], &[u8]); 3] = [
+            (b"password", b"somesalt"),
+            (b"hunter42", b"somesalt"),
+            (b"hunter42", b"othersalt"),
+        ];
+
+        let mut outputs = [[0u8; 32]; 3];
+        let mut memory = [Block::default(); 32];
+        argon2
+            .hash_many_into(
+                &inputs,
+                &mut outputs
+                    .iter_mut()
+                    .map(|out| &mut out[..])
+                    .collect::<Vec<_>>(),
+                &mut memory,
+            )
+            .unwrap();
+
+        for ((pwd, salt), out) in inputs.iter().zip(&outputs) {
+            let mut expected = [0u8; 32];
+            argon2.hash_password_into(pwd, salt, &mut expected).unwrap();
+            assert_eq!(out, &expected);
+        }
+
+        // Inputs are validated independently
+        let inputs: [(&[u8], &[u8]); 2] = [(b"password", b"somesalt"), (b"password", b"short")];
+        let mut outputs = [[0u8; 32]; 2];
+        assert_eq!(
+            argon2.hash_many_into(
+                &inputs,
+                &mut outputs
+                    .iter_mut()
+                    .map(|out| &mut out[..])
+                    .collect::<Vec<_>>(),
+                &mut memory,
+            ),
+            Err(Error::SaltTooShort)
+        );
+    }
+
+    #[test]
+    fn hash_with_secret() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone());
+        let salt = EXAMPLE_SALT.as_bytes();
+
+        for secret in [&b"pepper"[..], b"other pepper"] {
+            let mut expected = [0u8; 32];
+            Argon2::new_with_secret(secret, Algorithm::Argon2id, Version::V0x13, params.clone())
+                .unwrap()
+                .hash_password_into(EXAMPLE_PASSWORD, salt, &mut expected)
+                .unwrap();
+
+            let mut out = [0u8; 32];
+            let mut memory = [Block::default(); 32];
+            argon2
+                .hash_password_into_with_secret(
+                    EXAMPLE_PASSWORD,
+                    salt,
+                    secret,
+                    &mut out,
+                    &mut memory,
+                )
+                .unwrap();
+            assert_eq!(out, expected);
+        }
+    }
+
+    #[test]
+    fn verify_phc_hash_last_byte() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        let mut hash = argon2
+            .hash_password(EXAMPLE_PASSWORD, EXAMPLE_SALT)
+            .unwrap();
+        assert_eq!(argon2.verify_password(EXAMPLE_PASSWORD, &hash), Ok(()));
+
+        let mut bytes = hash.hash.unwrap().as_bytes().to_vec();
+        *bytes.last_mut().unwrap() ^= 1;
+        hash.hash = Some(password_hash::Output::new(&bytes).unwrap());
+        assert_eq!(
+            argon2.verify_password(EXAMPLE_PASSWORD, &hash),
+            Err(password_hash::Error::Password)
+        );
+    }
+
+    /// Hashes missing a required cost parameter used to be verified with the
+    /// default for that parameter, but are now rejected.
+    #[test]
+    fn verify_password_missing_params() {
+        use alloc::string::ToString;
+
+        let argon2 = Argon2::default();
+        let hash = argon2
+            .hash_password(EXAMPLE_PASSWORD, EXAMPLE_SALT)
+            .unwrap()
+            .to_string();
+        assert!(hash.contains(",p=1$"));
+
+        let hash = hash.replace(",p=1$", "$");
+        let hash = PasswordHash::new(&hash).unwrap();
+        assert_eq!(
+            argon2.verify_password(EXAMPLE_PASSWORD, &hash),
+            Err(password_hash::Error::ParamNameInvalid)
+        );
+        assert!(argon2.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn verify_password_strict() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone());
+        let hash = argon2
+            .hash_password(EXAMPLE_PASSWORD, EXAMPLE_SALT)
+            .unwrap();
+        assert_eq!(
+            argon2.verify_password_strict(EXAMPLE_PASSWORD, &hash),
+            Ok(())
+        );
+        assert_eq!(
+            argon2.verify_password_strict(b"wrong", &hash),
+            Err(password_hash::Error::Password)
+        );
+
+        for other in [
+            Argon2::new(Algorithm::Argon2i, Version::V0x13, params.clone()),
+            Argon2::new(Algorithm::Argon2id, Version::V0x10, params),
+        ] {
+            assert_eq!(other.verify_password(EXAMPLE_PASSWORD, &hash), Ok(()));
+            assert_eq!(
+                other.verify_password_strict(EXAMPLE_PASSWORD, &hash),
+                Err(Error::AlgorithmInvalid.into())
+            );
+        }
+    }
+
+    #[test]
+    fn verify_password_with_secrets() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let old = Argon2::new_with_secret(
+            b"old pepper",
+            Algorithm::Argon2id,
+            Version::V0x13,
+            params.clone(),
+        )
+        .unwrap();
+        let new =
+            Argon2::new_with_secret(b"new pepper", Algorithm::Argon2id, Version::V0x13, params)
+                .unwrap();
+
+        let old_hash = old.hash_password(EXAMPLE_PASSWORD, EXAMPLE_SALT).unwrap();
+        let new_hash = new.hash_password(EXAMPLE_PASSWORD, EXAMPLE_SALT).unwrap();
+        let secrets: [&[u8]; 2] = [b"new pepper", b"old pepper"];
+
+        for hash in [&old_hash, &new_hash] {
+            assert!(new
+                .verify_password_with_secrets(EXAMPLE_PASSWORD, hash, &secrets)
+                .is_ok());
+            assert_eq!(
+                new.verify_password_with_secrets(b"wrong", hash, &secrets),
+                Err(password_hash::Error::Password)
+            );
+        }
+
+        assert_eq!(
+            new.verify_password_with_secrets(EXAMPLE_PASSWORD, &old_hash, &[b"new pepper"]),
+            Err(password_hash::Error::Password)
+        );
+        assert_eq!(
+            new.verify_password_with_secrets(EXAMPLE_PASSWORD, &old_hash, &[]),
+            Err(password_hash::Error::Password)
+        );
+    }
+
+    #[test]
+    fn verify_password_with_memory() {
+        let params = Params::new(64, 2, 2, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2i, Version::V0x13, params);
+        let hash = argon2
+            .hash_password(EXAMPLE_PASSWORD, EXAMPLE_SALT)
+            .unwrap();
+
+        // The hash's own parameters are used
+        let verifier = Argon2::default();
+        let mut memory = [Block::default(); 64];
+        assert_eq!(
+            verifier.verify_password_with_memory(EXAMPLE_PASSWORD, &hash, &mut memory),
+            Ok(())
+        );
+        assert_eq!(
+            verifier.verify_password_with_memory(b"wrong", &hash, &mut memory),
+            Err(password_hash::Error::Password)
+        );
+        assert_eq!(
+            verifier.verify_password_with_memory(EXAMPLE_PASSWORD, &hash, &mut memory[..63]),
+            Err(Error::MemoryTooLittle.into())
+        );
+    }
+
+    #[test]
+    fn data_and_keyid() {
+        let hash = |data: &[u8], keyid: &[u8]| {
+            let mut builder = crate::ParamsBuilder::new();
+            builder.m_cost(32).unwrap();
+            builder.t_cost(1).unwrap();
+            builder.data(data).unwrap();
+            builder.keyid(keyid).unwrap();
+
+            let argon2 = Argon2::from(builder.params().unwrap());
+            let mut out = [0u8; 32];
+            argon2
+                .hash_password_into(EXAMPLE_PASSWORD, EXAMPLE_SALT.as_bytes(), &mut out)
+                .unwrap();
+            out
+        };
+
+        let max_data = [0x04; Params::MAX_DATA_LEN];
+        let max_keyid = [0x05; Params::MAX_KEYID_LEN];
+
+        // Associated data is mixed into the hash, the key ID isn't
+        assert_ne!(hash(&[], &[]), hash(&[0x04], &[]));
+        assert_ne!(hash(&[0x04], &[]), hash(&[0x05], &[]));
+        assert_ne!(hash(&max_data, &[]), hash(&max_data[1..], &[]));
+        assert_eq!(hash(&max_data, &[]), hash(&max_data, &max_keyid));
+    }
+
+    #[test]
+    fn empty_output() {
+        for output_len in [None, Some(32)] {
+            let params = Params::new(32, 2, 1, output_len).unwrap();
+            let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+            let mut memory = [Block::default(); 32];
+
+            assert_eq!(
+                argon2.hash_password_into_with_memory(
+                    EXAMPLE_PASSWORD,
+                    EXAMPLE_SALT.as_bytes(),
+                    &mut [],
+                    &mut memory
+                ),
+                Err(Error::OutputTooShort)
+            );
+        }
+    }
+
+    #[test]
+    fn output_len_mismatch() {
+        let params = Params::new(32, 2, 1, Some(32)).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        for actual in [16, 64] {
+            let mut out = vec![0u8; actual];
+            assert_eq!(
+                argon2.hash_password_into(EXAMPLE_PASSWORD, EXAMPLE_SALT.as_bytes(), &mut out),
+                Err(Error::OutputLenMismatch {
+                    expected: 32,
+                    actual
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn hash_password_configured_output_len() {
+        const LEN: usize = password_hash::Output::MIN_LENGTH;
+        let params = Params::new(32, 2, 1, Some(LEN)).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        let hash = argon2
+            .hash_password(EXAMPLE_PASSWORD, EXAMPLE_SALT)
+            .unwrap();
+        assert_eq!(hash.hash.unwrap().len(), LEN);
+        argon2.verify_password(EXAMPLE_PASSWORD, &hash).unwrap();
+
+        // Valid for raw hashing, but too short for a PHC string
+        let params = Params::new(32, 2, 1, Some(LEN - 1)).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        assert_eq!(
+            argon2.hash_password(EXAMPLE_PASSWORD, EXAMPLE_SALT),
+            Err(password_hash::Error::OutputTooShort)
+        );
+    }
+
+    /// Pre-hashing digest of the Argon2id v0x13 test vector from RFC 9106.
+    #[test]
+    fn initial_hash() {
+        let mut builder = crate::ParamsBuilder::new();
+        builder.m_cost(32).unwrap();
+        builder.t_cost(3).unwrap();
+        builder.p_cost(4).unwrap();
+        builder.data(&[0x04; 12]).unwrap();
+        let params = builder.params().unwrap();
+
+        let argon2 =
+            Argon2::new_with_secret(&[0x03; 8], Algorithm::Argon2id, Version::V0x13, params)
+                .unwrap();
+
+        assert_eq!(
+            argon2.initial_hash(&[0x01; 32], &[0x02; 16], 32),
+            hex_literal::hex!(
+                "
+                28 89 de 48 7e b4 2a e5 00 c0 00 7e d9 25 2f 10
+                69 ea de c4 0d 57 65 b4 85 de 6d c2 43 7a 67 b8
+                54 6a 2f 0a cc 1a 08 82 db 8f cf 74 71 4b 47 2e
+                94 df 42 1a 5d a1 11 2f fa 11 43 43 70 a1 e9 97
+                "
+            )
+        );
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    #[should_panic(expected = "salt is too long")]
+    fn initial_hash_salt_too_long() {
+        // Zero-initialized, so the pages are never actually allocated
+        let salt = vec![0u8; crate::MAX_SALT_LEN + 1];
+        Argon2::default().initial_hash(EXAMPLE_PASSWORD, &salt, 32);
+    }
+
+    #[test]
+    fn peek_algorithm() {
+        use alloc::string::ToString;
+
+        for algorithm in Algorithm::all() {
+            let hash = Argon2::new(
+                algorithm,
+                Version::V0x13,
+                Params::new(32, 1, 1, None).unwrap(),
+            )
+            .hash_password(EXAMPLE_PASSWORD, EXAMPLE_SALT)
+            .unwrap()
+            .to_string();
+            assert_eq!(super::peek_algorithm(&hash), Some(algorithm));
+        }
+
+        assert_eq!(
+            super::peek_algorithm("$argon2id$v=19$m=4096,t=3,p=1$invalid"),
+            Some(Algorithm::Argon2id)
+        );
+
+        for hash in [
+            "$2b$12$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMUW",
+            "$scrypt$ln=16,r=8,p=1$c29tZXNhbHQ$aGFzaA",
+            "$argon2$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$aGFzaA",
+            "$ARGON2ID$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$aGFzaA",
+            "argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$aGFzaA",
+            "$argon2id",
+            "",
+        ] {
+            assert_eq!(super::peek_algorithm(hash), None, "{}", hash);
+        }
+    }
+
+    #[test]
+    fn salt_from_bytes() {
+        let salt = crate::salt_from_bytes(&[0x02; 16]).unwrap();
+        assert_eq!(salt.as_str(), "AgICAgICAgICAgICAgICAg");
+
+        let mut buf = [0u8; 16];
+        assert_eq!(salt.b64_decode(&mut buf).unwrap(), [0x02; 16]);
+        assert!(Argon2::default()
+            .hash_password(EXAMPLE_PASSWORD, &salt)
+            .is_ok());
+
+        assert!(crate::salt_from_bytes(&[0x02; 48]).is_ok());
+        assert_eq!(
+            crate::salt_from_bytes(&[0x02; 7]).unwrap_err(),
+            Error::SaltTooShort
+        );
+        assert_eq!(
+            crate::salt_from_bytes(&[0x02; 49]).unwrap_err(),
+            Error::SaltTooLong
+        );
+    }
+
+    #[test]
+    fn derive_key_with_short_salt() {
+        let argon2 = Argon2::from(Params::new(32, 2, 1, None).unwrap());
+        let mut memory = [Block::default(); 32];
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(EXAMPLE_PASSWORD, b"somesalt", &mut expected)
+            .unwrap();
+        let mut key = [0u8; 32];
+        argon2
+            .derive_key_with_short_salt(EXAMPLE_PASSWORD, b"somesalt", &mut key, &mut memory)
+            .unwrap();
+        assert_eq!(key, expected);
+
+        let mut unsalted = [0u8; 32];
+        argon2
+            .derive_key_with_short_salt(EXAMPLE_PASSWORD, &[], &mut unsalted, &mut memory)
+            .unwrap();
+        assert_ne!(unsalted, expected);
+
+        // The regular paths are still strict
+        assert_eq!(
+            argon2.derive_key(EXAMPLE_PASSWORD, &[], &mut key),
+            Err(Error::SaltTooShort)
+        );
+    }
+
+    #[test]
+    fn derive_key() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = EXAMPLE_SALT.as_bytes();
+
+        let mut expected = [0u8; 64];
+        argon2
+            .hash_password_into(EXAMPLE_PASSWORD, salt, &mut expected)
+            .unwrap();
+
+        let mut key = [0u8; 64];
+        argon2.derive_key(EXAMPLE_PASSWORD, salt, &mut key).unwrap();
+        assert_eq!(key, expected);
+
+        assert_eq!(
+            argon2.derive_key(EXAMPLE_PASSWORD, b"short", &mut key),
+            Err(Error::SaltTooShort)
+        );
+    }
+
+    #[test]
+    fn derive_key_multi_kilobyte() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = EXAMPLE_SALT.as_bytes();
+
+        let mut key = vec![0u8; 8192];
+        argon2.derive_key(EXAMPLE_PASSWORD, salt, &mut key).unwrap();
+
+        // The output length is hashed, so a shorter key isn't a prefix
+        let mut short_key = [0u8; 64];
+        argon2
+            .derive_key(EXAMPLE_PASSWORD, salt, &mut short_key)
+            .unwrap();
+        assert_ne!(key[..64], short_key);
+
+        // The last block of the chain isn't left empty
+        assert!(key[8192 - 64..].iter().any(|&b| b != 0));
+    }
+
+    /// Memory which can't be allocated on 32-bit targets is rejected before
+    /// trying to allocate it.
+    #[cfg(target_pointer_width = "32")]
+    #[test]
+    fn memory_too_large_for_target() {
+        let params = Params::new(Params::MAX_M_COST, 1, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        let mut out = [0u8; 32];
+        assert_eq!(
+            argon2.hash_password_into(EXAMPLE_PASSWORD, EXAMPLE_SALT.as_bytes(), &mut out),
+            Err(Error::MemoryTooMuch)
+        );
+        assert!(matches!(
+            Argon2WithMemory::new(argon2),
+            Err(Error::MemoryTooMuch)
+        ));
+    }
+
+    #[test]
+    fn from_params_and_version() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+
+        for version in [Version::V0x10, Version::V0x13] {
+            let argon2 = Argon2::from_params_and_version(params.clone(), version);
+            assert_eq!(argon2.algorithm, Algorithm::Argon2id);
+            assert_eq!(argon2.version, version);
+            assert_eq!(argon2.params(), &params);
+        }
+
+        let argon2 = Argon2::from_params_and_version(params.clone(), Version::default());
+        let mut expected = [0u8; 32];
+        Argon2::from(params)
+            .hash_password_into(EXAMPLE_PASSWORD, EXAMPLE_SALT.as_bytes(), &mut expected)
+            .unwrap();
+        let mut out = [0u8; 32];
+        argon2
+            .hash_password_into(EXAMPLE_PASSWORD, EXAMPLE_SALT.as_bytes(), &mut out)
+            .unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn hash_with_exact_memory() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = EXAMPLE_SALT.as_bytes();
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(EXAMPLE_PASSWORD, salt, &mut expected)
+            .unwrap();
+
+        let mut out = [0u8; 32];
+        let mut blocks = [Block::default(); 33];
+        argon2
+            .hash_password_into_with_memory_exact(
+                EXAMPLE_PASSWORD,
+                salt,
+                &mut out,
+                &mut blocks[..32],
+            )
+            .unwrap();
+        assert_eq!(out, expected);
+
+        assert_eq!(
+            argon2.hash_password_into_with_memory_exact(
+                EXAMPLE_PASSWORD,
+                salt,
+                &mut out,
+                &mut blocks[..31]
+            ),
+            Err(Error::MemoryTooLittle)
+        );
+        assert_eq!(
+            argon2.hash_password_into_with_memory_exact(
+                EXAMPLE_PASSWORD,
+                salt,
+                &mut out,
+                &mut blocks
+            ),
+            Err(Error::MemoryTooMuch)
+        );
+
+        // The lenient version uses the first blocks of a larger buffer
+        argon2
+            .hash_password_into_with_memory(EXAMPLE_PASSWORD, salt, &mut out, &mut blocks)
+            .unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn verify_raw_hash() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = EXAMPLE_SALT.as_bytes();
+        let mut memory = [Block::default(); 32];
+
+        // Outputs longer than a single BLAKE2b hash are compared in chunks
+        for len in [32, 100] {
+            let mut hash = vec![0u8; len];
+            argon2
+                .hash_password_into(EXAMPLE_PASSWORD, salt, &mut hash)
+                .unwrap();
+
+            assert_eq!(
+                argon2.verify_password_into(EXAMPLE_PASSWORD, salt, &hash, &mut memory),
+                Ok(())
+            );
+            assert_eq!(
+                argon2.verify_password_into(b"wrong password", salt, &hash, &mut memory),
+                Err(Error::PwdInvalid)
+            );
+
+            hash[len - 1] ^= 1;
+            assert_eq!(
+                argon2.verify_password_into(EXAMPLE_PASSWORD, salt, &hash, &mut memory),
+                Err(Error::PwdInvalid)
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hash_from_reader() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = EXAMPLE_SALT.as_bytes();
+        let pwd: Vec<u8> = (0..3000).map(|i| i as u8).collect();
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(&pwd, salt, &mut expected)
+            .unwrap();
+
+        let mut out = [0u8; 32];
+        let mut memory = [Block::default(); 32];
+        argon2
+            .hash_reader_into(&pwd[..], 3000, salt, &mut out, &mut memory)
+            .unwrap();
+        assert_eq!(out, expected);
+
+        assert_eq!(
+            argon2.hash_reader_into(&pwd[..2999], 3000, salt, &mut out, &mut memory),
+            Err(Error::PwdReadFailed)
+        );
+    }
+
+    #[test]
+    fn hash_with_reused_memory() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let mut reusable = Argon2WithMemory::new(argon2.clone()).unwrap();
+
+        for (pwd, salt) in [(b"password", b"somesalt"), (b"hunter42", b"somesalt")] {
+            let mut expected = [0u8; 32];
+            argon2.hash_password_into(pwd, salt, &mut expected).unwrap();
+
+            let mut out = [0u8; 32];
+            reusable.hash_password_reuse(pwd, salt, &mut out).unwrap();
+            assert_eq!(out, expected);
+        }
+    }
+
+    /// Assert that all memory blocks have been zeroized.
+    #[cfg(feature = "zeroize")]
+    fn assert_zeroized(memory: &[Block]) {
+        for (i, block) in memory.iter().enumerate() {
+            assert!(
+                block.words().iter().all(|&word| word == 0),
+                "block {} wasn't zeroized",
+                i
+            );
+        }
+    }
+
+    /// With the `zeroize` feature, every function hashing into memory
+    /// blocks provided by the caller wipes them before returning, whether
+    /// the result is a hash, a successful or failed verification or an
+    /// error.
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn memory_zeroized_after_hashing() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = EXAMPLE_SALT.as_bytes();
+        let mut out = [0u8; 32];
+        let mut memory = [Block::default(); 32];
+
+        argon2
+            .hash_password_into_with_memory(EXAMPLE_PASSWORD, salt, &mut out, &mut memory)
+            .unwrap();
+        assert_ne!(out, [0u8; 32]);
+        assert_zeroized(&memory);
+
+        argon2
+            .hash_password_into_with_memory_exact(EXAMPLE_PASSWORD, salt, &mut out, &mut memory)
+            .unwrap();
+        assert_zeroized(&memory);
+
+        argon2
+            .hash_password_into_with_secret(
+                EXAMPLE_PASSWORD,
+                salt,
+                b"pepper",
+                &mut out,
+                &mut memory,
+            )
+            .unwrap();
+        assert_zeroized(&memory);
+
+        argon2
+            .derive_key_with_short_salt(EXAMPLE_PASSWORD, b"", &mut out, &mut memory)
+            .unwrap();
+        assert_zeroized(&memory);
+
+        argon2
+            .hash_password_into_with_memory(EXAMPLE_PASSWORD, salt, &mut out, &mut memory)
+            .unwrap();
+        for pwd in [EXAMPLE_PASSWORD, b"wrong"] {
+            let _ = argon2.verify_password_into(pwd, salt, &out, &mut memory);
+            assert_zeroized(&memory);
+        }
+
+        let hash = argon2
+            .hash_password(EXAMPLE_PASSWORD, EXAMPLE_SALT)
+            .unwrap();
+        argon2
+            .verify_password_with_memory(EXAMPLE_PASSWORD, &hash, &mut memory)
+            .unwrap();
+        assert_zeroized(&memory);
+
+        #[cfg(feature = "std")]
+        {
+            argon2
+                .hash_reader_into(
+                    EXAMPLE_PASSWORD,
+                    EXAMPLE_PASSWORD.len() as u32,
+                    salt,
+                    &mut out,
+                    &mut memory,
+                )
+                .unwrap();
+            assert_zeroized(&memory);
+        }
+
+        // Cancelled before the first slice is filled
+        let cancel = AtomicBool::new(true);
+        assert_eq!(
+            argon2.hash_password_into_with_memory_cancellable(
+                EXAMPLE_PASSWORD,
+                salt,
+                &mut out,
+                &mut memory,
+                &cancel
+            ),
+            Err(Error::Cancelled)
+        );
+        assert_zeroized(&memory);
+    }
+
+    #[test]
+    fn cancellation() {
+        let params = Params::new(64, 2, 2, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let salt = EXAMPLE_SALT.as_bytes();
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(EXAMPLE_PASSWORD, salt, &mut expected)
+            .unwrap();
+
+        // An unset flag doesn't affect the output
+        let mut out = [0u8; 32];
+        let mut blocks = [Block::default(); 64];
+        argon2
+            .hash_password_into_with_memory_cancellable(
+                EXAMPLE_PASSWORD,
+                salt,
+                &mut out,
+                &mut blocks,
+                &AtomicBool::new(false),
+            )
+            .unwrap();
+        assert_eq!(out, expected);
+
+        let res = argon2.hash_password_into_with_memory_cancellable(
+            EXAMPLE_PASSWORD,
+            salt,
+            &mut out,
+            &mut blocks,
+            &AtomicBool::new(true),
+        );
+        assert_eq!(res, Err(Error::Cancelled));
+
+        #[cfg(feature = "zeroize")]
+        assert_zeroized(&blocks);
+    }
 }
@@ -2,8 +2,16 @@
 
 use crate::Block;
 
-/// Number of synchronization points between lanes per pass
-pub(crate) const SYNC_POINTS: u32 = 4;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+/// Number of synchronization points between lanes per pass, i.e. the
+/// number of slices each lane is divided into.
+///
+/// This is fixed to 4 by the Argon2 specification and can't be configured.
+/// Every lane consists of `SYNC_POINTS` segments, so the segment length is
+/// [`Params::lane_length`][`crate::Params::lane_length`] divided by it.
+pub const SYNC_POINTS: u32 = 4;
 
 /// Structure containing references to the memory blocks
 pub(crate) struct Memory<'a> {
@@ -45,3 +53,12 @@ impl<'a> Memory<'a> {
         self.segment_length
     }
 }
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for Memory<'_> {
+    fn zeroize(&mut self) {
+        for block in self.data.iter_mut() {
+            block.zeroize();
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Memory blocks backed by an anonymous memory map.
+
+#![allow(unsafe_code)]
+
+use crate::{Block, Error, Params, Result};
+use core::slice;
+use memmap2::MmapMut;
+
+/// Memory blocks backed by an anonymous memory map rather than the heap.
+///
+/// This is intended for very large memory costs (e.g. several GiB), where
+/// the pages are left to be managed by the OS. It can be passed to
+/// [`Argon2::hash_password_into_with_memory`][`crate::Argon2::hash_password_into_with_memory`].
+#[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+pub struct MmapBlocks {
+    /// Memory map, a multiple of [`Block::SIZE`] in length
+    map: MmapMut,
+}
+
+impl MmapBlocks {
+    /// Map the memory blocks required by the given [`Params`].
+    ///
+    /// Returns [`Error::MemoryMapFailed`] if the memory couldn't be mapped.
+    pub fn new(params: &Params) -> Result<Self> {
+        crate::policy::check_global_max_memory(params.memory_bytes())?;
+
+        let len = params.checked_memory_bytes()?;
+
+        let map = MmapMut::map_anon(len).map_err(|_| Error::MemoryMapFailed)?;
+        Ok(Self { map })
+    }
+
+    /// Advise the OS to back the map with transparent huge pages.
+    ///
+    /// Huge pages are only a performance hint, so this falls back to
+    /// regular pages if they're unavailable.
+    #[cfg(feature = "hugepages")]
+    pub(crate) fn advise_huge_pages(&self) {
+        #[cfg(target_os = "linux")]
+        let _ = self.map.advise(memmap2::Advice::HugePage);
+    }
+}
+
+impl AsRef<[Block]> for MmapBlocks {
+    fn as_ref(&self) -> &[Block] {
+        // SAFETY: the map is page aligned, its length is a multiple of
+        // `Block::SIZE`, and any contents are a valid `Block`
+        unsafe { slice::from_raw_parts(self.map.as_ptr().cast(), self.map.len() / Block::SIZE) }
+    }
+}
+
+impl AsMut<[Block]> for MmapBlocks {
+    fn as_mut(&mut self) -> &mut [Block] {
+        // SAFETY: the map is page aligned, its length is a multiple of
+        // `Block::SIZE`, and any contents are a valid `Block`
+        unsafe {
+            slice::from_raw_parts_mut(self.map.as_mut_ptr().cast(), self.map.len() / Block::SIZE)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MmapBlocks;
+    use crate::{Algorithm, Argon2, Block, Params, ParamsBuilder, Result, Version};
+    use hex_literal::hex;
+
+    /// Argon2id v0x13 known answer test from RFC 9106.
+    fn hash_with<B: AsRef<[Block]> + AsMut<[Block]>>(new: fn(&Params) -> Result<B>) {
+        let mut builder = ParamsBuilder::new();
+        builder.m_cost(32).unwrap();
+        builder.t_cost(3).unwrap();
+        builder.p_cost(4).unwrap();
+        builder.data(&[0x04; 12]).unwrap();
+        let params = builder.params().unwrap();
+
+        let secret = [0x03; 8];
+        let mut blocks = new(&params).unwrap();
+        assert_eq!(blocks.as_ref().len(), params.block_count());
+
+        let ctx =
+            Argon2::new_with_secret(&secret, Algorithm::Argon2id, Version::V0x13, params).unwrap();
+        let mut out = [0u8; 32];
+        ctx.hash_password_into_with_memory(&[0x01; 32], &[0x02; 16], &mut out, &mut blocks)
+            .unwrap();
+
+        assert_eq!(
+            out,
+            hex!(
+                "
+                0d 64 0d f5 8d 78 76 6c 08 c0 37 a3 4a 8b 53 c9
+                d0 1e f0 45 2d 75 b6 5e b5 25 20 e9 6b 01 e6 59
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn hash_with_mmap_blocks() {
+        hash_with(MmapBlocks::new);
+    }
+
+    #[cfg(feature = "hugepages")]
+    #[test]
+    fn hash_with_huge_page_blocks() {
+        hash_with(crate::HugePageBlocks::new);
+    }
+}
@@ -0,0 +1,225 @@
+//! Argon2 context which owns its secret key.
+
+use crate::{Algorithm, Argon2, Block, Error, Params, Result, Version, MAX_SECRET_LEN};
+use alloc::vec::Vec;
+use core::fmt;
+use zeroize::Zeroize;
+
+#[cfg(feature = "password-hash")]
+use {
+    crate::{PasswordHash, PasswordHasher},
+    password_hash::{Decimal, Ident, Salt},
+};
+
+/// Argon2 context which owns its secret key a.k.a. "pepper", and zeroizes
+/// it when dropped.
+///
+/// This is useful when the secret is loaded at runtime, e.g. from a secrets
+/// manager, and there's no convenient place to borrow it from for the
+/// lifetime of an [`Argon2`] context. Hashes are identical to those computed
+/// by an [`Argon2`] context created with [`Argon2::new_with_secret`].
+#[derive(Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+pub struct Argon2Owned {
+    /// Algorithm to use
+    algorithm: Algorithm,
+
+    /// Version number
+    version: Version,
+
+    /// Algorithm parameters
+    params: Params,
+
+    /// Key array
+    secret: Vec<u8>,
+
+    /// Maximum number of threads
+    max_threads: usize,
+}
+
+impl Argon2Owned {
+    /// Create a new Argon2 context which takes ownership of `secret`.
+    pub fn new(
+        secret: Vec<u8>,
+        algorithm: Algorithm,
+        version: Version,
+        params: Params,
+    ) -> Result<Self> {
+        if MAX_SECRET_LEN < secret.len() {
+            return Err(Error::SecretTooLong);
+        }
+
+        Ok(Self {
+            algorithm,
+            version,
+            params,
+            secret,
+            max_threads: usize::MAX,
+        })
+    }
+
+    /// Limit the number of threads used to fill memory, see
+    /// [`Argon2::with_max_threads`].
+    pub fn with_max_threads(mut self, max_threads: usize) -> Result<Self> {
+        if max_threads == 0 {
+            return Err(Error::ThreadsTooFew);
+        }
+
+        self.max_threads = max_threads;
+        Ok(self)
+    }
+
+    /// Get an [`Argon2`] context borrowing the secret key, which provides
+    /// the full hashing API.
+    pub fn argon2(&self) -> Argon2<'_> {
+        Argon2 {
+            algorithm: self.algorithm,
+            version: self.version,
+            params: self.params.clone(),
+            secret: Some(&self.secret),
+            max_threads: self.max_threads,
+        }
+    }
+
+    /// Get default configured [`Params`].
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Hash a password and associated parameters into the provided output
+    /// buffer, see [`Argon2::hash_password_into`].
+    pub fn hash_password_into(&self, pwd: &[u8], salt: &[u8], out: &mut [u8]) -> Result<()> {
+        self.argon2().hash_password_into(pwd, salt, out)
+    }
+
+    /// Hash a password and associated parameters into the provided output
+    /// buffer, using the provided memory blocks, see
+    /// [`Argon2::hash_password_into_with_memory`].
+    pub fn hash_password_into_with_memory(
+        &self,
+        pwd: &[u8],
+        salt: &[u8],
+        out: &mut [u8],
+        memory_blocks: impl AsMut<[Block]>,
+    ) -> Result<()> {
+        self.argon2()
+            .hash_password_into_with_memory(pwd, salt, out, memory_blocks)
+    }
+
+    /// Verify a password against a previously computed raw hash in constant
+    /// time, see [`Argon2::verify_password_into`].
+    pub fn verify_password_into(
+        &self,
+        pwd: &[u8],
+        salt: &[u8],
+        expected: &[u8],
+        memory_blocks: impl AsMut<[Block]>,
+    ) -> Result<()> {
+        self.argon2()
+            .verify_password_into(pwd, salt, expected, memory_blocks)
+    }
+}
+
+impl fmt::Debug for Argon2Owned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Argon2Owned")
+            .field("algorithm", &self.algorithm)
+            .field("version", &self.version)
+            .field("params", &self.params)
+            .field("secret", &format_args!("<redacted>"))
+            .field("max_threads", &self.max_threads)
+            .finish()
+    }
+}
+
+impl Drop for Argon2Owned {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+#[cfg(feature = "password-hash")]
+#[cfg_attr(docsrs, doc(cfg(feature = "password-hash")))]
+impl PasswordHasher for Argon2Owned {
+    type Params = Params;
+
+    fn hash_password<'a, S>(
+        &self,
+        password: &[u8],
+        salt: &'a S,
+    ) -> password_hash::Result<PasswordHash<'a>>
+    where
+        S: AsRef<str> + ?Sized,
+    {
+        self.argon2().hash_password(password, salt)
+    }
+
+    fn hash_password_customized<'a>(
+        &self,
+        password: &[u8],
+        alg_id: Option<Ident<'a>>,
+        version: Option<Decimal>,
+        params: Params,
+        salt: impl Into<Salt<'a>>,
+    ) -> password_hash::Result<PasswordHash<'a>> {
+        self.argon2()
+            .hash_password_customized(password, alg_id, version, params, salt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Argon2Owned;
+    use crate::{Algorithm, Argon2, Params, Version};
+
+    #[cfg(feature = "password-hash")]
+    use crate::{PasswordHasher, PasswordVerifier};
+
+    const SECRET: &[u8] = b"pepper from a secrets manager";
+
+    fn argon2_owned() -> Argon2Owned {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        Argon2Owned::new(SECRET.to_vec(), Algorithm::Argon2id, Version::V0x13, params).unwrap()
+    }
+
+    #[test]
+    fn hash_matches_borrowed_secret() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let borrowed =
+            Argon2::new_with_secret(SECRET, Algorithm::Argon2id, Version::V0x13, params).unwrap();
+
+        let mut expected = [0u8; 32];
+        borrowed
+            .hash_password_into(b"password", b"somesalt", &mut expected)
+            .unwrap();
+
+        let mut out = [0u8; 32];
+        argon2_owned()
+            .hash_password_into(b"password", b"somesalt", &mut out)
+            .unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[cfg(feature = "password-hash")]
+    #[test]
+    fn hash_password_matches_borrowed_secret() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let borrowed =
+            Argon2::new_with_secret(SECRET, Algorithm::Argon2id, Version::V0x13, params).unwrap();
+        let owned = argon2_owned();
+
+        let hash = owned.hash_password(b"password", "c29tZXNhbHQ").unwrap();
+        assert_eq!(
+            hash,
+            borrowed.hash_password(b"password", "c29tZXNhbHQ").unwrap()
+        );
+        assert!(owned.verify_password(b"password", &hash).is_ok());
+        assert!(owned.verify_password(b"wrong password", &hash).is_err());
+    }
+
+    #[test]
+    fn debug_redacts_secret() {
+        let debug = format!("{:?}", argon2_owned());
+        assert!(debug.contains("secret: <redacted>"), "{}", debug);
+    }
+}
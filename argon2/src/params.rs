@@ -1,12 +1,18 @@
 //! Argon2 password hash parameters.
 
-use crate::{Error, Result, SYNC_POINTS};
+use crate::{Algorithm, Block, Error, Result, SYNC_POINTS};
 use base64ct::{Base64Unpadded as B64, Encoding};
-use core::str::FromStr;
+use core::{fmt, str::FromStr};
 
 #[cfg(feature = "password-hash")]
 use password_hash::{ParamsString, PasswordHash};
 
+#[cfg(feature = "std")]
+use {
+    crate::{Argon2, Version},
+    std::time::{Duration, Instant},
+};
+
 /// Argon2 password hash parameters.
 ///
 /// These are parameters which can be encoded into a PHC hash string.
@@ -77,9 +83,29 @@ impl Params {
     /// Minimum digest size in bytes.
     pub const MIN_OUTPUT_LEN: usize = 4;
 
-    /// Maximum digest size in bytes.
+    /// Maximum digest size in bytes, i.e. 2^32 - 1, the maximum of the
+    /// specification.
+    ///
+    /// Outputs longer than 64 bytes are built from a chain of BLAKE2b
+    /// hashes, see [`blake2b_long`][`crate::blake2b_long`].
     pub const MAX_OUTPUT_LEN: usize = 0xFFFFFFFF;
 
+    /// Minimum memory cost of [`SecurityLevel::Acceptable`] parameters,
+    /// from the OWASP Password Storage Cheat Sheet (19 MiB).
+    pub const ACCEPTABLE_M_COST: u32 = 19 * 1024;
+
+    /// Minimum number of passes of [`SecurityLevel::Acceptable`]
+    /// parameters, from the OWASP Password Storage Cheat Sheet.
+    pub const ACCEPTABLE_T_COST: u32 = 2;
+
+    /// Minimum memory cost of [`SecurityLevel::Strong`] parameters, from
+    /// the second recommended option of RFC 9106 (64 MiB).
+    pub const STRONG_M_COST: u32 = 64 * 1024;
+
+    /// Minimum number of passes of [`SecurityLevel::Strong`] parameters,
+    /// from the second recommended option of RFC 9106.
+    pub const STRONG_T_COST: u32 = 3;
+
     /// Create new parameters.
     pub fn new(m_cost: u32, t_cost: u32, p_cost: u32, output_len: Option<usize>) -> Result<Self> {
         let mut builder = ParamsBuilder::new();
@@ -94,6 +120,180 @@ impl Params {
         builder.params()
     }
 
+    /// Create new parameters in a const context, e.g. for a configuration
+    /// which is fixed at compile time.
+    ///
+    /// This applies the same validations as [`Params::new`], but panics
+    /// instead of returning an error, so invalid parameters fail the build
+    /// when evaluated in a const:
+    ///
+    /// ```
+    /// use argon2::{Block, Params};
+    ///
+    /// const PARAMS: Params = Params::new_const(256, 2, 1, None);
+    ///
+    /// let memory = [Block::default(); PARAMS.block_count()];
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If any of the parameters is invalid.
+    pub const fn new_const(
+        m_cost: u32,
+        t_cost: u32,
+        p_cost: u32,
+        output_len: Option<usize>,
+    ) -> Self {
+        assert!(m_cost >= Self::MIN_M_COST, "memory cost is too small");
+        assert!(m_cost <= Self::MAX_M_COST, "memory cost is too large");
+        assert!(t_cost >= Self::MIN_T_COST, "time cost is too small");
+        assert!(p_cost >= Self::MIN_P_COST, "not enough threads");
+        assert!(p_cost <= Self::MAX_P_COST, "too many threads");
+        assert!(m_cost >= p_cost * 8, "memory cost is too small");
+
+        if let Some(len) = output_len {
+            assert!(len >= Self::MIN_OUTPUT_LEN, "output is too short");
+            assert!(len <= Self::MAX_OUTPUT_LEN, "output is too long");
+        }
+
+        Self {
+            m_cost,
+            t_cost,
+            p_cost,
+            keyid: KeyId::EMPTY,
+            data: AssociatedData::EMPTY,
+            output_len,
+        }
+    }
+
+    /// Create new parameters with the memory size expressed in mebibytes.
+    ///
+    /// Memory cost recommendations are usually phrased in MiB (e.g. "use
+    /// 64 MiB"), whereas `m_cost` is expressed in KiB. This converts `mib`
+    /// to the equivalent `m_cost` and applies the same validations as
+    /// [`Params::new`], including that the memory is at least 8 blocks per
+    /// lane.
+    pub fn from_memory_mib(mib: u32, t_cost: u32, p_cost: u32) -> Result<Self> {
+        let m_cost = mib.checked_mul(1024).ok_or(Error::MemoryTooMuch)?;
+        Self::new(m_cost, t_cost, p_cost, None)
+    }
+
+    /// Parameters for interactive use, e.g. logging in to an online service:
+    /// `m_cost` = 65536 (64 MiB), `t_cost` = 2, `p_cost` = 1.
+    ///
+    /// These match libsodium's `crypto_pwhash_*_INTERACTIVE` limits for
+    /// Argon2id.
+    pub fn interactive() -> Self {
+        Self::preset(64 * 1024, 2)
+    }
+
+    /// Parameters for operations where a longer delay is acceptable:
+    /// `m_cost` = 262144 (256 MiB), `t_cost` = 3, `p_cost` = 1.
+    ///
+    /// These match libsodium's `crypto_pwhash_*_MODERATE` limits for
+    /// Argon2id.
+    pub fn moderate() -> Self {
+        Self::preset(256 * 1024, 3)
+    }
+
+    /// Parameters for highly sensitive data and non-interactive use, e.g.
+    /// deriving an encryption key: `m_cost` = 1048576 (1 GiB),
+    /// `t_cost` = 4, `p_cost` = 1.
+    ///
+    /// These match libsodium's `crypto_pwhash_*_SENSITIVE` limits for
+    /// Argon2id.
+    pub fn sensitive() -> Self {
+        Self::preset(1024 * 1024, 4)
+    }
+
+    /// Parameters recommended for Argon2id by the OWASP Password Storage
+    /// Cheat Sheet as of 2023: `m_cost` = 19456 (19 MiB), `t_cost` = 2,
+    /// `p_cost` = 1.
+    ///
+    /// Unlike [`Params::default`], these values are pinned to a dated
+    /// recommendation and will not change. Newer recommendations will be
+    /// added as separate constructors.
+    pub fn owasp_2023() -> Self {
+        Self::preset(19 * 1024, 2)
+    }
+
+    /// Default parameters for the given [`Algorithm`].
+    ///
+    /// Argon2id and Argon2d use [`Params::default`]. Argon2i uses the same
+    /// memory cost with twice as many passes (`t_cost` = 6): its
+    /// data-independent memory access pattern makes time-memory tradeoff
+    /// attacks cheaper than against the other variants, which is compensated
+    /// with more passes. libsodium's Argon2i limits likewise use twice as
+    /// many passes as its Argon2id ones.
+    pub fn default_for(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Argon2i => Self::preset(Self::DEFAULT_M_COST, 2 * Self::DEFAULT_T_COST),
+            Algorithm::Argon2d | Algorithm::Argon2id => Self::default(),
+        }
+    }
+
+    /// Parameters with the given memory and time costs and the default
+    /// degree of parallelism.
+    fn preset(m_cost: u32, t_cost: u32) -> Self {
+        Self {
+            m_cost,
+            t_cost,
+            ..Self::default()
+        }
+    }
+
+    /// Pick parameters so that hashing a password takes roughly `target` on
+    /// the current machine, using at most `max_memory_bytes` of memory.
+    ///
+    /// This times hashes with a single pass, using as much memory as
+    /// allowed. The memory is halved while a single pass takes longer than
+    /// `target`, after which the number of passes is chosen to fill the
+    /// remaining time. A warm-up hash is run before timing.
+    ///
+    /// The result is only as accurate as the timings, which are affected by
+    /// the load on the machine. A degree of parallelism of 1 is used.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn calibrate(
+        target: Duration,
+        max_memory_bytes: usize,
+        algorithm: Algorithm,
+        version: Version,
+    ) -> Result<Self> {
+        let max_m_cost = max_memory_bytes / Block::SIZE;
+        let mut m_cost = u32::try_from(max_m_cost)
+            .unwrap_or(u32::MAX)
+            .min(Self::MAX_M_COST);
+
+        if m_cost < Self::MIN_M_COST {
+            return Err(Error::MemoryTooLittle);
+        }
+
+        let time_pass = |m_cost| -> Result<Duration> {
+            let params = Self::new(m_cost, 1, Self::DEFAULT_P_COST, None)?;
+            let argon2 = Argon2::new(algorithm, version, params);
+            let mut out = [0u8; Self::DEFAULT_OUTPUT_LEN];
+
+            let start = Instant::now();
+            argon2.hash_password_into(b"password", b"calibration salt", &mut out)?;
+            Ok(start.elapsed())
+        };
+
+        // Warm-up
+        time_pass(m_cost)?;
+        let mut elapsed = time_pass(m_cost)?;
+
+        while elapsed > target && m_cost / 2 >= Self::MIN_M_COST {
+            m_cost /= 2;
+            elapsed = time_pass(m_cost)?;
+        }
+
+        let t_cost = (target.as_secs_f64() / elapsed.as_secs_f64().max(f64::MIN_POSITIVE))
+            .clamp(Self::MIN_T_COST.into(), Self::MAX_T_COST.into()) as u32;
+
+        Self::new(m_cost, t_cost, Self::DEFAULT_P_COST, None)
+    }
+
     /// Memory size, expressed in kilobytes, between 1 and (2^32)-1.
     ///
     /// Value is an integer in decimal (1 to 10 digits).
@@ -101,6 +301,31 @@ impl Params {
         self.m_cost
     }
 
+    /// Memory size in KiB, i.e. the same value as [`Params::m_cost`] with
+    /// the unit spelled out.
+    pub fn m_cost_kib(&self) -> u32 {
+        self.m_cost
+    }
+
+    /// Memory size in bytes, i.e. [`Params::m_cost`] times 1024.
+    ///
+    /// This is the memory size as configured; the memory actually used for
+    /// hashing is given by [`Params::memory_bytes`].
+    pub fn m_cost_bytes(&self) -> u64 {
+        u64::from(self.m_cost) * 1024
+    }
+
+    /// Memory size in KiB which is actually used for hashing, i.e.
+    /// [`Params::m_cost`] rounded down to a multiple of 4 blocks per lane
+    /// (and at least 8 blocks per lane). This is [`Params::block_count`] as
+    /// a `u32`.
+    ///
+    /// This is only informational: the requested `m_cost` is hashed into the
+    /// output, so it is the value which must be stored to verify a hash.
+    pub fn effective_m_cost(&self) -> u32 {
+        self.segment_length() * SYNC_POINTS * self.lanes()
+    }
+
     /// Number of iterations, between 1 and (2^32)-1.
     ///
     /// Value is an integer in decimal (1 to 10 digits).
@@ -146,15 +371,39 @@ impl Params {
         self.output_len
     }
 
-    /// Get the number of lanes.
-    pub(crate) fn lanes(&self) -> u32 {
+    /// Get the number of lanes, i.e. [`Params::p_cost`].
+    ///
+    /// The memory consists of this many lanes of [`Params::lane_length`]
+    /// blocks each.
+    pub fn lanes(&self) -> u32 {
         self.p_cost
     }
 
-    /// Get the segment length given the configured `m_cost` and `p_cost`.
+    /// Get the number of passes over the memory, i.e. [`Params::t_cost`].
+    pub fn iterations(&self) -> u32 {
+        self.t_cost
+    }
+
+    /// Get the number of blocks per lane: [`Params::block_count`] divided by
+    /// [`Params::lanes`].
+    ///
+    /// Every lane consists of [`SYNC_POINTS`][`crate::SYNC_POINTS`] segments
+    /// of [`Params::segment_length`] blocks, which are separated by
+    /// synchronization points.
+    pub fn lane_length(&self) -> u32 {
+        self.segment_length() * SYNC_POINTS
+    }
+
+    /// Get the segment length given the configured `m_cost` and `p_cost`:
+    /// [`Params::lane_length`] divided by [`SYNC_POINTS`][`crate::SYNC_POINTS`].
     ///
     /// Minimum memory_blocks = 8*`L` blocks, where `L` is the number of lanes.
-    pub(crate) fn segment_length(&self) -> u32 {
+    ///
+    /// As in the reference implementation, `m_cost` is rounded down to a
+    /// multiple of `4*L` blocks, so every lane is a whole number of segments.
+    /// [`Params::block_count`] is derived from the segment length, so the
+    /// blocks in excess of `m_cost` are never part of the memory.
+    pub fn segment_length(&self) -> u32 {
         let memory_blocks = if self.m_cost < 2 * SYNC_POINTS * self.lanes() {
             2 * SYNC_POINTS * self.lanes()
         } else {
@@ -165,11 +414,167 @@ impl Params {
     }
 
     /// Get the number of blocks required given the configured `m_cost` and `p_cost`.
-    pub fn block_count(&self) -> usize {
-        (self.segment_length() * self.p_cost * SYNC_POINTS) as usize
+    pub const fn block_count(&self) -> usize {
+        Self::blocks_for(self.m_cost, self.p_cost)
+    }
+
+    /// Get the number of blocks required for the given `m_cost` and
+    /// `p_cost`, i.e. [`Params::block_count`] for parameters with these
+    /// values.
+    ///
+    /// This can be used in const contexts to size a stack-allocated buffer
+    /// for [`Argon2::hash_password_into_with_memory`], e.g.
+    /// `[Block::default(); Params::blocks_for(M_COST, P_COST)]`.
+    ///
+    /// `p_cost` must not be zero.
+    ///
+    /// [`Argon2::hash_password_into_with_memory`]: crate::Argon2::hash_password_into_with_memory
+    pub const fn blocks_for(m_cost: u32, p_cost: u32) -> usize {
+        let memory_blocks = if m_cost < 2 * SYNC_POINTS * p_cost {
+            2 * SYNC_POINTS * p_cost
+        } else {
+            m_cost
+        };
+
+        let segment_length = memory_blocks / (p_cost * SYNC_POINTS);
+        (segment_length * p_cost * SYNC_POINTS) as usize
+    }
+
+    /// Get the amount of memory in bytes required given the configured
+    /// `m_cost` and `p_cost`, i.e. [`Params::block_count`] times
+    /// [`Block::SIZE`].
+    ///
+    /// Saturates at `usize::MAX` if the amount can't be represented on the
+    /// current platform.
+    pub fn memory_bytes(&self) -> usize {
+        self.block_count().saturating_mul(Block::SIZE)
+    }
+
+    /// Get the amount of memory in bytes required given the configured
+    /// `m_cost` and `p_cost` like [`Params::memory_bytes`], checking that it
+    /// can be allocated on the current platform.
+    ///
+    /// Returns [`Error::MemoryTooMuch`] if the amount exceeds `isize::MAX`
+    /// bytes, the largest possible allocation, e.g. for a memory cost of 2
+    /// GiB or more on 32-bit targets.
+    pub fn checked_memory_bytes(&self) -> Result<usize> {
+        self.block_count()
+            .checked_mul(Block::SIZE)
+            .filter(|&bytes| bytes <= isize::MAX as usize)
+            .ok_or(Error::MemoryTooMuch)
+    }
+
+    /// Rate these parameters against current recommendations.
+    ///
+    /// The memory and time costs must both reach the thresholds of a level:
+    /// [`Params::STRONG_M_COST`] and [`Params::STRONG_T_COST`] for
+    /// [`SecurityLevel::Strong`], [`Params::ACCEPTABLE_M_COST`] and
+    /// [`Params::ACCEPTABLE_T_COST`] for [`SecurityLevel::Acceptable`].
+    /// Anything weaker is [`SecurityLevel::TooWeak`].
+    ///
+    /// This doesn't enforce a policy: it is up to the application to log a
+    /// warning or reject weak parameters. The algorithm isn't part of
+    /// [`Params`], and the recommendations assume Argon2id.
+    pub fn security_level(&self) -> SecurityLevel {
+        if self.m_cost >= Self::STRONG_M_COST && self.t_cost >= Self::STRONG_T_COST {
+            SecurityLevel::Strong
+        } else if self.m_cost >= Self::ACCEPTABLE_M_COST && self.t_cost >= Self::ACCEPTABLE_T_COST {
+            SecurityLevel::Acceptable
+        } else {
+            SecurityLevel::TooWeak
+        }
+    }
+
+    /// Get a summary of the costs of these parameters, e.g. for logging.
+    ///
+    /// It doesn't include the key identifier or associated data.
+    pub fn as_summary(&self) -> ParamsSummary {
+        ParamsSummary {
+            m_cost: self.m_cost,
+            t_cost: self.t_cost,
+            p_cost: self.p_cost,
+            output_len: self.output_len,
+            memory_bytes: self.memory_bytes(),
+        }
+    }
+
+    /// Do these parameters have the same costs as `other`, i.e. the same
+    /// `m_cost`, `t_cost` and `p_cost`?
+    ///
+    /// Unlike `==`, this ignores the output length, key identifier and
+    /// associated data, e.g. to check that a stored hash was computed with
+    /// the costs required by a policy.
+    pub fn matches(&self, other: &Params) -> bool {
+        self.m_cost == other.m_cost && self.t_cost == other.t_cost && self.p_cost == other.p_cost
+    }
+
+    /// Get the computational cost of hashing with these parameters in
+    /// abstract work units: the number of blocks times the number of passes,
+    /// i.e. roughly `m_cost * t_cost`.
+    ///
+    /// Saturates at `u64::MAX`.
+    pub fn work_units(&self) -> u64 {
+        (self.block_count() as u64).saturating_mul(self.t_cost.into())
     }
 }
 
+/// Rating of [`Params`] against current recommendations, see
+/// [`Params::security_level`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum SecurityLevel {
+    /// Below the minimum recommendations.
+    TooWeak,
+
+    /// Meets the OWASP minimum recommendations.
+    Acceptable,
+
+    /// Meets the RFC 9106 recommendations for memory-constrained
+    /// environments.
+    Strong,
+}
+
+/// Summary of the costs of [`Params`], see [`Params::as_summary`].
+///
+/// It is displayed in the same form as the parameters of a PHC string, e.g.
+/// `m=19456,t=2,p=1`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ParamsSummary {
+    /// Memory cost in KiB, see [`Params::m_cost`]
+    pub m_cost: u32,
+
+    /// Number of passes, see [`Params::t_cost`]
+    pub t_cost: u32,
+
+    /// Degree of parallelism, see [`Params::p_cost`]
+    pub p_cost: u32,
+
+    /// Output length in bytes, if configured
+    pub output_len: Option<usize>,
+
+    /// Amount of memory used for hashing in bytes, see
+    /// [`Params::memory_bytes`]
+    pub memory_bytes: usize,
+}
+
+impl fmt::Display for ParamsSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m={},t={},p={}", self.m_cost, self.t_cost, self.p_cost)
+    }
+}
+
+/// Get the number of memory blocks required for the given `m_cost` and
+/// `p_cost`, without constructing [`Params`].
+///
+/// This applies the same rounding as [`Params::block_count`], and returns
+/// the same value for any valid `m_cost` and `p_cost`, e.g. to size a buffer
+/// before the parameters are validated. It is equivalent to
+/// [`Params::blocks_for`].
+///
+/// `p_cost` must not be zero.
+pub const fn block_count(m_cost: u32, p_cost: u32) -> usize {
+    Params::blocks_for(m_cost, p_cost)
+}
+
 impl Default for Params {
     fn default() -> Params {
         Params {
@@ -199,6 +604,12 @@ macro_rules! param_buf {
             /// Maximum length in bytes
             pub const MAX_LEN: usize = $max_len;
 
+            /// Empty value
+            const EMPTY: Self = Self {
+                bytes: [0u8; Self::MAX_LEN],
+                len: 0,
+            };
+
             #[doc = "Create a new"]
             #[doc = $name]
             #[doc = "from a slice."]
@@ -277,12 +688,27 @@ param_buf!(
     "Associated data"
 );
 
+/// Read the parameters of a parsed PHC string, e.g. to inspect the cost of
+/// a stored hash without verifying a password against it.
+///
+/// The `m`, `t` and `p` parameters are required, and `keyid` and `data` are
+/// optional. The output length is set to the length of the hash, if any.
+///
+/// A missing required parameter is reported as
+/// [`password_hash::Error::ParamNameInvalid`], the same as an unknown one,
+/// as `password-hash` has no dedicated error for it.
 #[cfg(feature = "password-hash")]
 #[cfg_attr(docsrs, doc(cfg(feature = "password-hash")))]
 impl<'a> TryFrom<&'a PasswordHash<'a>> for Params {
     type Error = password_hash::Error;
 
     fn try_from(hash: &'a PasswordHash<'a>) -> password_hash::Result<Self> {
+        for required in ["m", "t", "p"] {
+            if hash.params.get(required).is_none() {
+                return Err(password_hash::Error::ParamNameInvalid);
+            }
+        }
+
         let mut builder = ParamsBuilder::new();
 
         for (ident, value) in hash.params.iter() {
@@ -352,6 +778,9 @@ impl<'a> TryFrom<&Params> for ParamsString {
 pub struct ParamsBuilder {
     /// Parameters being constructed
     params: Params,
+
+    /// Maximum number of work units, see [`ParamsBuilder::max_work_units`]
+    max_work_units: Option<u64>,
 }
 
 impl ParamsBuilder {
@@ -359,6 +788,7 @@ impl ParamsBuilder {
     pub fn new() -> Self {
         Self {
             params: Params::default(),
+            max_work_units: None,
         }
     }
 
@@ -376,6 +806,15 @@ impl ParamsBuilder {
         Ok(self)
     }
 
+    /// Set memory size, expressed in bytes.
+    ///
+    /// The size is rounded to the nearest KiB and validated like
+    /// [`ParamsBuilder::m_cost`].
+    pub fn m_cost_bytes(&mut self, bytes: usize) -> Result<&mut Self> {
+        let kib = (bytes as u64 + 512) / 1024;
+        self.m_cost(kib.try_into().map_err(|_| Error::MemoryTooMuch)?)
+    }
+
     /// Set number of iterations, between 1 and (2^32)-1.
     pub fn t_cost(&mut self, t_cost: u32) -> Result<&mut Self> {
         if t_cost < Params::MIN_T_COST {
@@ -404,7 +843,9 @@ impl ParamsBuilder {
 
     /// Set key identifier.
     ///
-    /// Must be 8-bytes or less.
+    /// Must be 8-bytes or less ([`Params::MAX_KEYID_LEN`]), otherwise
+    /// [`Error::KeyIdTooLong`] is returned. The key identifier is only
+    /// stored in PHC strings and doesn't affect the output.
     pub fn keyid(&mut self, keyid: &[u8]) -> Result<&mut Self> {
         self.params.keyid = KeyId::new(keyid)?;
         Ok(self)
@@ -412,13 +853,23 @@ impl ParamsBuilder {
 
     /// Set associated data.
     ///
-    /// Must be 32-bytes or less.
+    /// Must be 32-bytes or less ([`Params::MAX_DATA_LEN`]), otherwise
+    /// [`Error::AdTooLong`] is returned. The associated data is hashed into
+    /// the initial hash, so it affects the output.
     pub fn data(&mut self, bytes: &[u8]) -> Result<&mut Self> {
         self.params.data = AssociatedData::new(bytes)?;
         Ok(self)
     }
 
     /// Set length of the output (in bytes).
+    ///
+    /// Must be between [`Params::MIN_OUTPUT_LEN`] and
+    /// [`Params::MAX_OUTPUT_LEN`], otherwise [`Error::OutputTooShort`] or
+    /// [`Error::OutputTooLong`] is returned, so an invalid length is rejected
+    /// here rather than when hashing. Once set, hashing into an output of a
+    /// different length fails with [`Error::OutputLenMismatch`], and PHC
+    /// strings are computed with this length. Note that the output of a PHC
+    /// string must be at least 10 bytes long.
     pub fn output_len(&mut self, len: usize) -> Result<&mut Self> {
         if len < Params::MIN_OUTPUT_LEN {
             return Err(Error::OutputTooShort);
@@ -432,17 +883,34 @@ impl ParamsBuilder {
         Ok(self)
     }
 
+    /// Set the maximum computational cost of the parameters in
+    /// [work units](Params::work_units), e.g. to bound the cost of
+    /// parameters from an untrusted source.
+    pub fn max_work_units(&mut self, max_work_units: u64) -> &mut Self {
+        self.max_work_units = Some(max_work_units);
+        self
+    }
+
     /// Get the finished [`Params`].
     ///
     /// This performs further validations to ensure that the given parameters
     /// are compatible with each other, and will return an error if they are not.
     ///
-    /// The main validation is that `m_cost` < `p_cost * 8`
+    /// The main validation is that `m_cost` >= `p_cost * 8`, i.e. at least
+    /// two blocks per segment, returning [`Error::MemoryTooLittle`] otherwise.
+    /// If a [maximum](ParamsBuilder::max_work_units) is set, parameters
+    /// exceeding it are rejected with [`Error::WorkExceedsLimit`].
     pub fn params(self) -> Result<Params> {
         if self.params.m_cost < self.params.p_cost * 8 {
             return Err(Error::MemoryTooLittle);
         }
 
+        if let Some(max_work_units) = self.max_work_units {
+            if self.params.work_units() > max_work_units {
+                return Err(Error::WorkExceedsLimit);
+            }
+        }
+
         Ok(self.params)
     }
 }
@@ -460,6 +928,357 @@ mod tests {
 
     use super::*;
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn params_calibrate() {
+        let params = Params::calibrate(
+            Duration::from_millis(20),
+            64 * 1024,
+            Algorithm::Argon2id,
+            Version::V0x13,
+        )
+        .unwrap();
+
+        assert!(params.m_cost() <= 64);
+        assert!(params.t_cost() >= 1);
+        assert_eq!(params.p_cost(), 1);
+
+        assert_eq!(
+            Params::calibrate(
+                Duration::from_millis(20),
+                7 * 1024,
+                Algorithm::Argon2id,
+                Version::V0x13,
+            ),
+            Err(Error::MemoryTooLittle)
+        );
+    }
+
+    #[test]
+    fn params_memory_bytes() {
+        let params = Params::new(19 * 1024, 2, 1, None).unwrap();
+        assert_eq!(params.memory_bytes(), 19 * 1024 * 1024);
+
+        // Rounded down to a multiple of 4 blocks per lane
+        let params = Params::new(4099, 1, 2, None).unwrap();
+        assert_eq!(params.memory_bytes(), 4096 * 1024);
+
+        let params = Params::new(Params::MAX_M_COST, 1, 1, None).unwrap();
+        assert_eq!(
+            params.memory_bytes(),
+            (params.block_count() as u64 * 1024)
+                .try_into()
+                .unwrap_or(usize::MAX)
+        );
+    }
+
+    #[cfg(feature = "password-hash")]
+    #[test]
+    fn params_from_password_hash() {
+        let hash = PasswordHash::new(
+            "$argon2id$v=19$m=4096,t=3,p=2,keyid=8PDw8A,data=DwAPAA$c29tZXNhbHQ$AAECAwQFBgcICQoLDA0ODw",
+        )
+        .unwrap();
+        let params = Params::try_from(&hash).unwrap();
+        assert_eq!(params.m_cost(), 4096);
+        assert_eq!(params.t_cost(), 3);
+        assert_eq!(params.p_cost(), 2);
+        assert_eq!(params.keyid(), [0xf0; 4]);
+        assert_eq!(params.data(), [0x0f, 0x00, 0x0f, 0x00]);
+        assert_eq!(params.output_len(), Some(16));
+
+        let hash = PasswordHash::new("$argon2id$v=19$m=4096,p=2$c29tZXNhbHQ").unwrap();
+        assert_eq!(
+            Params::try_from(&hash),
+            Err(password_hash::Error::ParamNameInvalid)
+        );
+
+        let hash = PasswordHash::new("$argon2id$v=19$m=4,t=3,p=2$c29tZXNhbHQ").unwrap();
+        assert!(Params::try_from(&hash).is_err());
+
+        let hash = PasswordHash::new("$argon2id$v=19$m=4096,t=3,p=2,x=1$c29tZXNhbHQ").unwrap();
+        assert_eq!(
+            Params::try_from(&hash),
+            Err(password_hash::Error::ParamNameInvalid)
+        );
+    }
+
+    #[test]
+    fn params_m_cost_min_per_lane() {
+        for p_cost in [1, 2, 3, 4, 255] {
+            assert_eq!(
+                Params::new(8 * p_cost - 1, 1, p_cost, None),
+                Err(Error::MemoryTooLittle)
+            );
+
+            let params = Params::new(8 * p_cost, 1, p_cost, None).unwrap();
+            assert_eq!(params.block_count(), 8 * p_cost as usize);
+        }
+
+        let mut builder = ParamsBuilder::new();
+        builder.m_cost(31).unwrap().p_cost(4).unwrap();
+        assert_eq!(builder.params(), Err(Error::MemoryTooLittle));
+    }
+
+    #[test]
+    fn checked_memory_bytes() {
+        let params = Params::new(2 * 1024 * 1024, 1, 1, None).unwrap();
+
+        // 2 GiB exceeds `isize::MAX` bytes on 32-bit targets
+        #[cfg(target_pointer_width = "32")]
+        assert_eq!(params.checked_memory_bytes(), Err(Error::MemoryTooMuch));
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(params.checked_memory_bytes(), Ok(params.memory_bytes()));
+
+        let params = Params::new(2 * 1024 * 1024 - 4, 1, 1, None).unwrap();
+        assert_eq!(params.checked_memory_bytes(), Ok(params.memory_bytes()));
+    }
+
+    #[test]
+    fn params_geometry() {
+        for (m_cost, t_cost, p_cost) in [(8, 1, 1), (1001, 3, 3), (4096, 2, 4), (19 * 1024, 2, 1)] {
+            let params = Params::new(m_cost, t_cost, p_cost, None).unwrap();
+            assert_eq!(params.lanes(), p_cost);
+            assert_eq!(params.iterations(), t_cost);
+            assert_eq!(
+                params.lane_length() as usize,
+                params.block_count() / p_cost as usize
+            );
+            assert_eq!(
+                params.segment_length(),
+                params.lane_length() / crate::SYNC_POINTS
+            );
+        }
+    }
+
+    #[test]
+    fn params_effective_m_cost() {
+        let params = Params::new(1000, 1, 3, None).unwrap();
+        assert_eq!(params.m_cost(), 1000);
+        assert_eq!(params.effective_m_cost(), 996);
+        assert_eq!(params.block_count(), 996);
+
+        let params = Params::new(1000, 1, 1, None).unwrap();
+        assert_eq!(params.effective_m_cost(), params.m_cost());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn params_m_cost_rounded_to_segments() {
+        for (m_cost, p_cost, block_count) in [(1001, 1, 1000), (1001, 3, 996), (4099, 2, 4096)] {
+            let params = Params::new(m_cost, 1, p_cost, None).unwrap();
+            assert_eq!(params.block_count(), block_count);
+            assert_eq!(
+                params.segment_length() * SYNC_POINTS * p_cost,
+                block_count as u32
+            );
+
+            // Additional memory is left untouched
+            let argon2 = crate::Argon2::from(params);
+            let mut out = [0u8; 32];
+            let mut memory = alloc::vec![Block::default(); block_count + 10];
+            argon2
+                .hash_password_into_with_memory(b"password", b"somesalt", &mut out, &mut memory)
+                .unwrap();
+
+            let mut expected = [0u8; 32];
+            argon2
+                .hash_password_into_with_memory(
+                    b"password",
+                    b"somesalt",
+                    &mut expected,
+                    &mut memory[..block_count],
+                )
+                .unwrap();
+
+            assert_eq!(out, expected);
+            assert!(memory[block_count..]
+                .iter()
+                .all(|block| block.iter().all(|&word| word == 0)));
+        }
+    }
+
+    #[test]
+    fn params_work_units() {
+        let params = Params::new(19 * 1024, 2, 1, None).unwrap();
+        assert_eq!(params.work_units(), 19 * 1024 * 2);
+
+        let params = Params::new(Params::MAX_M_COST, Params::MAX_T_COST, 1, None).unwrap();
+        assert_eq!(
+            params.work_units(),
+            params.block_count() as u64 * u64::from(u32::MAX)
+        );
+
+        let mut builder = ParamsBuilder::new();
+        builder.m_cost(1024).unwrap().t_cost(4).unwrap();
+        builder.max_work_units(4096);
+        assert_eq!(builder.clone().params().unwrap().work_units(), 4096);
+
+        builder.t_cost(5).unwrap();
+        assert_eq!(builder.params(), Err(Error::WorkExceedsLimit));
+    }
+
+    #[test]
+    fn params_presets() {
+        for (params, m_cost, t_cost) in [
+            (Params::interactive(), 65536, 2),
+            (Params::moderate(), 262144, 3),
+            (Params::sensitive(), 1048576, 4),
+        ] {
+            assert_eq!(params.m_cost(), m_cost);
+            assert_eq!(params.t_cost(), t_cost);
+            assert_eq!(params.p_cost(), 1);
+            assert_eq!(params, Params::new(m_cost, t_cost, 1, None).unwrap());
+        }
+    }
+
+    #[test]
+    fn params_default_for() {
+        assert_eq!(Params::default_for(Algorithm::Argon2id), Params::default());
+        assert_eq!(Params::default_for(Algorithm::Argon2d), Params::default());
+
+        let params = Params::default_for(Algorithm::Argon2i);
+        assert_eq!(params.m_cost(), Params::DEFAULT_M_COST);
+        assert_eq!(params.t_cost(), 6);
+        assert_eq!(params.p_cost(), Params::DEFAULT_P_COST);
+    }
+
+    #[test]
+    fn params_owasp_2023() {
+        let params = Params::owasp_2023();
+        assert_eq!(params.m_cost(), 19456);
+        assert_eq!(params.t_cost(), 2);
+        assert_eq!(params.p_cost(), 1);
+        assert_eq!(params.output_len(), None);
+    }
+
+    #[test]
+    fn params_blocks_for() {
+        const BLOCKS: usize = Params::blocks_for(4099, 2);
+        assert_eq!(BLOCKS, 4096);
+        assert_eq!(Params::blocks_for(1, 2), 16);
+
+        for (m_cost, p_cost) in [(8, 1), (19 * 1024, 1), (4099, 2), (65, 4), (1000, 3)] {
+            let params = Params::new(m_cost, 1, p_cost, None).unwrap();
+            assert_eq!(Params::blocks_for(m_cost, p_cost), params.block_count());
+        }
+    }
+
+    #[test]
+    fn params_new_const() {
+        const PARAMS: Params = Params::new_const(4099, 3, 2, Some(64));
+        const BLOCKS: usize = PARAMS.block_count();
+        assert_eq!(PARAMS, Params::new(4099, 3, 2, Some(64)).unwrap());
+        assert_eq!(BLOCKS, 4096);
+    }
+
+    #[test]
+    #[should_panic(expected = "memory cost is too small")]
+    fn params_new_const_invalid() {
+        Params::new_const(31, 1, 4, None);
+    }
+
+    #[test]
+    fn free_block_count() {
+        for p_cost in [1, 2, 3, 4, 7, 255] {
+            for m_cost in (8 * p_cost..8 * p_cost + 64).chain([4096, 19 * 1024, 65537]) {
+                let params = Params::new(m_cost, 1, p_cost, None).unwrap();
+                assert_eq!(block_count(m_cost, p_cost), params.block_count());
+            }
+        }
+    }
+
+    #[test]
+    fn params_security_level() {
+        for (m_cost, t_cost, level) in [
+            (19 * 1024 - 1, 2, SecurityLevel::TooWeak),
+            (19 * 1024, 1, SecurityLevel::TooWeak),
+            (19 * 1024, 2, SecurityLevel::Acceptable),
+            (64 * 1024, 2, SecurityLevel::Acceptable),
+            (64 * 1024 - 1, 3, SecurityLevel::Acceptable),
+            (64 * 1024, 3, SecurityLevel::Strong),
+        ] {
+            let params = Params::new(m_cost, t_cost, 1, None).unwrap();
+            assert_eq!(params.security_level(), level, "m={} t={}", m_cost, t_cost);
+        }
+
+        assert_eq!(Params::default().security_level(), SecurityLevel::TooWeak);
+        assert_eq!(
+            Params::owasp_2023().security_level(),
+            SecurityLevel::Acceptable
+        );
+        assert_eq!(Params::moderate().security_level(), SecurityLevel::Strong);
+    }
+
+    #[test]
+    fn params_summary() {
+        let summary = Params::new(19 * 1024, 2, 1, Some(64)).unwrap().as_summary();
+        assert_eq!(
+            summary,
+            ParamsSummary {
+                m_cost: 19 * 1024,
+                t_cost: 2,
+                p_cost: 1,
+                output_len: Some(64),
+                memory_bytes: 19 * 1024 * 1024,
+            }
+        );
+
+        #[cfg(feature = "alloc")]
+        assert_eq!(
+            alloc::string::ToString::to_string(&summary),
+            "m=19456,t=2,p=1"
+        );
+    }
+
+    #[test]
+    fn params_matches() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+
+        let mut builder = ParamsBuilder::new();
+        builder.m_cost(32).unwrap();
+        builder.t_cost(2).unwrap();
+        builder.p_cost(1).unwrap();
+        builder.keyid(&[0x01; 4]).unwrap();
+        builder.data(&[0x02; 4]).unwrap();
+        builder.output_len(64).unwrap();
+        let other = builder.params().unwrap();
+
+        assert_ne!(params, other);
+        assert!(params.matches(&other));
+        assert!(other.matches(&params));
+
+        for (m_cost, t_cost, p_cost) in [(64, 2, 1), (32, 3, 1), (32, 2, 2)] {
+            let other = Params::new(m_cost, t_cost, p_cost, None).unwrap();
+            assert!(!params.matches(&other));
+        }
+    }
+
+    #[test]
+    fn params_m_cost_units() {
+        let params = Params::new(64 * 1024, 2, 1, None).unwrap();
+        assert_eq!(params.m_cost_kib(), 64 * 1024);
+        assert_eq!(params.m_cost_bytes(), 64 * 1024 * 1024);
+
+        let mut builder = ParamsBuilder::new();
+        builder.m_cost_bytes(64 * 1024 * 1024).unwrap();
+        assert_eq!(builder.params().unwrap().m_cost(), 64 * 1024);
+
+        // Rounded to the nearest KiB
+        let mut builder = ParamsBuilder::new();
+        builder.m_cost_bytes(10 * 1024 + 511).unwrap();
+        assert_eq!(builder.clone().params().unwrap().m_cost(), 10);
+        builder.m_cost_bytes(10 * 1024 + 512).unwrap();
+        assert_eq!(builder.params().unwrap().m_cost(), 11);
+
+        assert_eq!(
+            ParamsBuilder::new()
+                .m_cost_bytes(Params::MIN_M_COST as usize * 1024 - 513)
+                .err(),
+            Some(Error::MemoryTooLittle)
+        );
+    }
+
     #[test]
     fn params_builder_bad_values() {
         let mut builder = ParamsBuilder::new();
@@ -486,6 +1305,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn params_builder_output_len_bounds() {
+        let mut builder = ParamsBuilder::new();
+
+        assert_eq!(
+            builder.output_len(Params::MIN_OUTPUT_LEN - 1),
+            Err(Error::OutputTooShort)
+        );
+        if let Some(len) = Params::MAX_OUTPUT_LEN.checked_add(1) {
+            assert_eq!(builder.output_len(len), Err(Error::OutputTooLong));
+        }
+        assert_eq!(builder.clone().params().unwrap().output_len(), None);
+
+        for len in [Params::MIN_OUTPUT_LEN, Params::MAX_OUTPUT_LEN] {
+            builder.output_len(len).unwrap();
+            assert_eq!(builder.clone().params().unwrap().output_len(), Some(len));
+        }
+
+        assert_eq!(
+            Params::new(32, 1, 1, Some(Params::MIN_OUTPUT_LEN - 1)),
+            Err(Error::OutputTooShort)
+        );
+    }
+
     #[test]
     fn params_builder_data_too_long() {
         let mut builder = ParamsBuilder::new();
@@ -499,4 +1342,21 @@ mod tests {
         let ret = builder.keyid(&[0u8; Params::MAX_KEYID_LEN + 1]);
         assert_eq!(ret, Err(Error::KeyIdTooLong));
     }
+
+    #[test]
+    fn params_from_memory_mib() {
+        let params = Params::from_memory_mib(64, 3, 4).unwrap();
+        assert_eq!(params.m_cost(), 64 * 1024);
+        assert_eq!(params.t_cost(), 3);
+        assert_eq!(params.p_cost(), 4);
+
+        assert_eq!(
+            Params::from_memory_mib(0, 3, 4),
+            Err(Error::MemoryTooLittle)
+        );
+        assert_eq!(
+            Params::from_memory_mib(u32::MAX, 3, 4),
+            Err(Error::MemoryTooMuch)
+        );
+    }
 }
@@ -0,0 +1,146 @@
+//! Heapless formatting of PHC strings.
+
+use crate::{Argon2, Error, Result};
+use base64ct::{Base64Unpadded, Encoding};
+use core::fmt::{self, Write};
+
+impl Argon2<'_> {
+    /// Write a salt and hash computed with this context as a PHC string,
+    /// i.e. `$argon2id$v=19$m=65536,t=3,p=4$<salt>$<hash>`, into `buf`
+    /// without allocating.
+    ///
+    /// The key ID and associated data of the [`Params`][`crate::Params`] are
+    /// included if set, and all binary values are encoded with the B64
+    /// encoding of the PHC string format, i.e. standard Base64 without
+    /// padding, so the result is the same as the string produced by
+    /// [`PasswordHasher::hash_password`][`crate::PasswordHasher::hash_password`]
+    /// for the same salt.
+    ///
+    /// Returns the number of bytes written, or [`Error::OutputTooShort`] if
+    /// `buf` is too short for the string.
+    pub fn write_phc_string(&self, salt: &[u8], hash: &[u8], buf: &mut [u8]) -> Result<usize> {
+        let mut writer = BufWriter { buf, pos: 0 };
+
+        write!(
+            writer,
+            "${}$v={}$m={},t={},p={}",
+            self.algorithm.as_str(),
+            self.version.as_u32(),
+            self.params.m_cost(),
+            self.params.t_cost(),
+            self.params.p_cost()
+        )
+        .map_err(|_| Error::OutputTooShort)?;
+
+        if !self.params.keyid().is_empty() {
+            writer.write_str(",keyid=")?;
+            writer.write_b64(self.params.keyid())?;
+        }
+
+        if !self.params.data().is_empty() {
+            writer.write_str(",data=")?;
+            writer.write_b64(self.params.data())?;
+        }
+
+        writer.write_str("$")?;
+        writer.write_b64(salt)?;
+        writer.write_str("$")?;
+        writer.write_b64(hash)?;
+
+        Ok(writer.pos)
+    }
+}
+
+/// Writer appending to a byte buffer, failing once it is full.
+struct BufWriter<'a> {
+    /// Output buffer
+    buf: &'a mut [u8],
+
+    /// Number of bytes written so far
+    pos: usize,
+}
+
+impl BufWriter<'_> {
+    /// Append `s`, returning [`Error::OutputTooShort`] if it doesn't fit.
+    fn write_str(&mut self, s: &str) -> Result<()> {
+        fmt::Write::write_str(self, s).map_err(|_| Error::OutputTooShort)
+    }
+
+    /// Append the B64 encoding of `input`, returning
+    /// [`Error::OutputTooShort`] if it doesn't fit.
+    fn write_b64(&mut self, input: &[u8]) -> Result<()> {
+        let encoded = Base64Unpadded::encode(input, &mut self.buf[self.pos..])
+            .map_err(|_| Error::OutputTooShort)?;
+        self.pos += encoded.len();
+        Ok(())
+    }
+}
+
+impl fmt::Write for BufWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let out = self
+            .buf
+            .get_mut(self.pos..self.pos + s.len())
+            .ok_or(fmt::Error)?;
+        out.copy_from_slice(s.as_bytes());
+        self.pos += s.len();
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "alloc", feature = "password-hash"))]
+mod tests {
+    use crate::{Algorithm, Argon2, Error, ParamsBuilder, PasswordHasher, Version};
+    use alloc::string::ToString;
+
+    #[test]
+    fn matches_hash_password() {
+        let mut builder = ParamsBuilder::new();
+        builder.m_cost(32).unwrap();
+        builder.t_cost(2).unwrap();
+        builder.p_cost(1).unwrap();
+        builder.keyid(&[0xf0; 4]).unwrap();
+        builder.data(&[0x0f, 0x00, 0x0f, 0x00]).unwrap();
+        let params = builder.params().unwrap();
+
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let expected = argon2
+            .hash_password(b"password", "c29tZXNhbHQ")
+            .unwrap()
+            .to_string();
+
+        let mut hash = [0u8; 32];
+        argon2
+            .hash_password_into(b"password", b"somesalt", &mut hash)
+            .unwrap();
+
+        let mut buf = [0u8; 128];
+        let len = argon2
+            .write_phc_string(b"somesalt", &hash, &mut buf)
+            .unwrap();
+        assert_eq!(&buf[..len], expected.as_bytes());
+
+        assert_eq!(
+            argon2.write_phc_string(b"somesalt", &hash, &mut buf[..len - 1]),
+            Err(Error::OutputTooShort)
+        );
+    }
+
+    #[test]
+    fn without_keyid_and_data() {
+        let argon2 = Argon2::default();
+        let mut buf = [0u8; 128];
+        let len = argon2
+            .write_phc_string(b"somesalt", &[0u8; 4], &mut buf)
+            .unwrap();
+        assert_eq!(
+            &buf[..len],
+            b"$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$AAAAAA"
+        );
+
+        assert_eq!(
+            argon2.write_phc_string(b"somesalt", &[0u8; 4], &mut buf[..10]),
+            Err(Error::OutputTooShort)
+        );
+    }
+}
@@ -0,0 +1,37 @@
+//! Process-wide memory policy.
+
+use crate::{Error, Result};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Global memory ceiling in bytes, where `usize::MAX` means unlimited.
+static GLOBAL_MAX_MEMORY: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Set a process-wide ceiling on the memory, in bytes, which may be
+/// allocated for Argon2's memory blocks.
+///
+/// Hashing or verifying with parameters which require more memory than this
+/// fails with [`Error::MemoryExceedsPolicy`] before any memory is allocated.
+/// The ceiling applies to every [`Argon2`][`crate::Argon2`] context in the
+/// process, independently of their individual configuration.
+///
+/// Pass `usize::MAX` to remove the ceiling, which is the default.
+pub fn set_global_max_memory(bytes: usize) {
+    GLOBAL_MAX_MEMORY.store(bytes, Ordering::Relaxed);
+}
+
+/// Get the process-wide memory ceiling in bytes, if one has been set with
+/// [`set_global_max_memory`].
+pub fn global_max_memory() -> Option<usize> {
+    match GLOBAL_MAX_MEMORY.load(Ordering::Relaxed) {
+        usize::MAX => None,
+        bytes => Some(bytes),
+    }
+}
+
+/// Ensure allocating `bytes` of memory is permitted by the global policy.
+pub(crate) fn check_global_max_memory(bytes: usize) -> Result<()> {
+    match global_max_memory() {
+        Some(max) if bytes > max => Err(Error::MemoryExceedsPolicy),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,338 @@
+//! Resumable hashing with serializable state.
+
+use crate::{
+    instance::Instance, Algorithm, Argon2, Block, Error, Memory, Params, Result, Version,
+    SYNC_POINTS,
+};
+use alloc::vec::Vec;
+use core::fmt;
+
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+/// Magic bytes at the start of a serialized [`ResumeState`].
+const MAGIC: [u8; 4] = *b"A2RS";
+
+/// Length of the header of a serialized [`ResumeState`], which precedes the
+/// memory blocks.
+const HEADER_LEN: usize = 36;
+
+/// Intermediate state of a resumable Argon2 computation: the memory blocks
+/// and the position of the next slice to fill.
+///
+/// Slices are filled for all lanes at once, so the state can only be
+/// captured at synchronization points, i.e. between slices.
+///
+/// The state records the [`Algorithm`], [`Version`], memory, time and
+/// parallelism costs and output length it was started with, and is checked
+/// against them before resuming. The secret, associated data and password
+/// only affect the first blocks, so a state can't be checked against them.
+///
+/// The memory is derived from the password, and must be protected like it
+/// when it is stored.
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub struct ResumeState {
+    /// Algorithm
+    algorithm: Algorithm,
+
+    /// Version
+    version: Version,
+
+    /// Memory cost
+    m_cost: u32,
+
+    /// Number of passes
+    t_cost: u32,
+
+    /// Degree of parallelism
+    p_cost: u32,
+
+    /// Output length in bytes
+    out_len: u32,
+
+    /// Next synchronization point to fill, counted from the first slice of
+    /// the first pass
+    next_sync_point: u64,
+
+    /// Memory blocks
+    blocks: Vec<Block>,
+}
+
+impl ResumeState {
+    /// Has all memory been filled, so that the computation can be finished
+    /// with [`Argon2::finish_resumable`]?
+    pub fn is_complete(&self) -> bool {
+        self.next_sync_point == self.sync_points()
+    }
+
+    /// Serialize the state, including the memory blocks.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.blocks.len() * Block::SIZE);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&self.algorithm.to_le_bytes());
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&self.m_cost.to_le_bytes());
+        bytes.extend_from_slice(&self.t_cost.to_le_bytes());
+        bytes.extend_from_slice(&self.p_cost.to_le_bytes());
+        bytes.extend_from_slice(&self.out_len.to_le_bytes());
+        bytes.extend_from_slice(&self.next_sync_point.to_le_bytes());
+
+        for block in &self.blocks {
+            bytes.extend_from_slice(&block.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    /// Deserialize a state serialized with [`ResumeState::to_bytes`].
+    ///
+    /// Returns [`Error::ResumeStateInvalid`] if it is malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_LEN || bytes[..4] != MAGIC {
+            return Err(Error::ResumeStateInvalid);
+        }
+
+        let word = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+
+        let algorithm = Algorithm::all()
+            .into_iter()
+            .find(|alg| alg.as_u32() == word(4))
+            .ok_or(Error::ResumeStateInvalid)?;
+        let version = Version::try_from(word(8)).map_err(|_| Error::ResumeStateInvalid)?;
+        let (m_cost, t_cost, p_cost, out_len) = (word(12), word(16), word(20), word(24));
+        let next_sync_point = u64::from_le_bytes(bytes[28..36].try_into().unwrap());
+
+        let params =
+            Params::new(m_cost, t_cost, p_cost, None).map_err(|_| Error::ResumeStateInvalid)?;
+        let memory = &bytes[HEADER_LEN..];
+
+        let memory_bytes = params
+            .checked_memory_bytes()
+            .map_err(|_| Error::ResumeStateInvalid)?;
+
+        if out_len == 0 || memory.len() != memory_bytes {
+            return Err(Error::ResumeStateInvalid);
+        }
+
+        let mut blocks = vec![Block::default(); params.block_count()];
+        for (block, chunk) in blocks.iter_mut().zip(memory.chunks_exact(Block::SIZE)) {
+            block.load(chunk);
+        }
+
+        let state = Self {
+            algorithm,
+            version,
+            m_cost,
+            t_cost,
+            p_cost,
+            out_len,
+            next_sync_point,
+            blocks,
+        };
+
+        if state.next_sync_point > state.sync_points() {
+            return Err(Error::ResumeStateInvalid);
+        }
+
+        Ok(state)
+    }
+
+    /// Total number of synchronization points, i.e. slices of all passes.
+    fn sync_points(&self) -> u64 {
+        u64::from(self.t_cost) * u64::from(SYNC_POINTS)
+    }
+
+    /// Check that the state was started with the given context.
+    fn check(&self, argon2: &Argon2<'_>) -> Result<()> {
+        let params = &argon2.params;
+
+        if self.algorithm != argon2.algorithm
+            || self.version != argon2.version
+            || self.m_cost != params.m_cost()
+            || self.t_cost != params.t_cost()
+            || self.p_cost != params.p_cost()
+        {
+            return Err(Error::ResumeStateInvalid);
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ResumeState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResumeState")
+            .field("algorithm", &self.algorithm)
+            .field("version", &self.version)
+            .field("m_cost", &self.m_cost)
+            .field("t_cost", &self.t_cost)
+            .field("p_cost", &self.p_cost)
+            .field("out_len", &self.out_len)
+            .field("next_sync_point", &self.next_sync_point)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for ResumeState {
+    fn drop(&mut self) {
+        for block in self.blocks.iter_mut() {
+            block.zeroize();
+        }
+    }
+}
+
+impl Argon2<'_> {
+    /// Start a resumable computation of an `out_len` byte hash of `pwd` and
+    /// `salt`.
+    ///
+    /// The memory is allocated and its first blocks are initialized, but no
+    /// slices are filled yet. Fill them with [`Argon2::resume_fill`], which
+    /// can be interrupted and continued from a [`ResumeState`] serialized in
+    /// the meantime, and compute the hash with [`Argon2::finish_resumable`].
+    /// The result is the same as with [`Argon2::hash_password_into`].
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    pub fn start_resumable(&self, pwd: &[u8], salt: &[u8], out_len: usize) -> Result<ResumeState> {
+        self.validate_inputs(pwd.len(), salt, out_len)?;
+        self.params.checked_memory_bytes()?;
+
+        #[cfg(feature = "std")]
+        crate::policy::check_global_max_memory(self.params.memory_bytes())?;
+
+        #[allow(unused_mut)]
+        let mut initial_hash = self.initial_hash(pwd, salt, out_len);
+        let mut state = ResumeState {
+            algorithm: self.algorithm,
+            version: self.version,
+            m_cost: self.params.m_cost(),
+            t_cost: self.params.t_cost(),
+            p_cost: self.params.p_cost(),
+            out_len: out_len.try_into().map_err(|_| Error::OutputTooLong)?,
+            next_sync_point: 0,
+            blocks: vec![Block::default(); self.params.block_count()],
+        };
+
+        let memory = Memory::new(&mut state.blocks, self.params.segment_length());
+        let result = Instance::fill_first(self, &initial_hash, memory);
+
+        #[cfg(feature = "zeroize")]
+        initial_hash.zeroize();
+
+        result.map(|()| state)
+    }
+
+    /// Fill up to `max_slices` further slices of a resumable computation
+    /// started with [`Argon2::start_resumable`].
+    ///
+    /// Every pass consists of 4 slices. Returns
+    /// [`Error::ResumeStateInvalid`] if the state was started with a
+    /// different algorithm, version or costs.
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    pub fn resume_fill(&self, state: &mut ResumeState, max_slices: u64) -> Result<()> {
+        state.check(self)?;
+
+        let start = state.next_sync_point;
+        let end = start.saturating_add(max_slices).min(state.sync_points());
+        let memory = Memory::new(&mut state.blocks, self.params.segment_length());
+        Instance::fill_range(self, memory, start..end)?;
+
+        state.next_sync_point = end;
+        Ok(())
+    }
+
+    /// Compute the hash of a completely filled resumable computation into
+    /// `out`, which must have the length it was started with.
+    ///
+    /// Returns [`Error::ResumeStateInvalid`] if the state doesn't match this
+    /// context or isn't complete.
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    pub fn finish_resumable(&self, mut state: ResumeState, out: &mut [u8]) -> Result<()> {
+        state.check(self)?;
+
+        if !state.is_complete() || out.len() != state.out_len as usize {
+            return Err(Error::ResumeStateInvalid);
+        }
+
+        let memory = Memory::new(&mut state.blocks, self.params.segment_length());
+        Instance::finalize_filled(self, memory, |blockhash| {
+            crate::blake2b_long(&[blockhash], out)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResumeState;
+    use crate::{Algorithm, Argon2, Error, Params, Version};
+
+    fn argon2() -> Argon2<'static> {
+        let params = Params::new(64, 3, 2, None).unwrap();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+    }
+
+    #[test]
+    fn matches_regular_hashing() {
+        let argon2 = argon2();
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(b"password", b"somesalt", &mut expected)
+            .unwrap();
+
+        let mut state = argon2
+            .start_resumable(b"password", b"somesalt", 32)
+            .unwrap();
+
+        // Checkpoint after every 5 slices
+        while !state.is_complete() {
+            argon2.resume_fill(&mut state, 5).unwrap();
+            state = ResumeState::from_bytes(&state.to_bytes()).unwrap();
+        }
+
+        let mut out = [0u8; 32];
+        argon2.finish_resumable(state, &mut out).unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn rejects_mismatched_state() {
+        let argon2 = argon2();
+        let mut state = argon2
+            .start_resumable(b"password", b"somesalt", 32)
+            .unwrap();
+
+        let other = Argon2::new(Algorithm::Argon2i, Version::V0x13, argon2.params().clone());
+        assert_eq!(
+            other.resume_fill(&mut state, 1),
+            Err(Error::ResumeStateInvalid)
+        );
+
+        let other = Argon2::from(Params::new(64, 2, 2, None).unwrap());
+        assert_eq!(
+            other.resume_fill(&mut state, 1),
+            Err(Error::ResumeStateInvalid)
+        );
+
+        // Incomplete
+        assert_eq!(
+            argon2.finish_resumable(state, &mut [0u8; 32]),
+            Err(Error::ResumeStateInvalid)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_bytes() {
+        let argon2 = argon2();
+        let bytes = argon2
+            .start_resumable(b"password", b"somesalt", 32)
+            .unwrap()
+            .to_bytes();
+
+        assert!(ResumeState::from_bytes(&bytes).is_ok());
+        assert!(ResumeState::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(ResumeState::from_bytes(&bytes[1..]).is_err());
+
+        let mut past_end = bytes.clone();
+        past_end[28] = 13;
+        assert!(ResumeState::from_bytes(&past_end).is_err());
+    }
+}
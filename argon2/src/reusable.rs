@@ -0,0 +1,47 @@
+//! Argon2 context with reusable memory.
+
+use crate::{Argon2, Block, Result};
+use alloc::vec::Vec;
+
+/// Argon2 context which owns the memory blocks used for hashing, so they can
+/// be reused across hashes instead of being allocated for each of them.
+///
+/// This is useful when repeatedly hashing with the same [`Params`][`crate::Params`],
+/// e.g. when verifying passwords on login. The memory isn't shared between
+/// threads: use one [`Argon2WithMemory`] per thread instead.
+#[derive(Clone)]
+pub struct Argon2WithMemory<'key> {
+    /// Argon2 context
+    argon2: Argon2<'key>,
+
+    /// Memory blocks, sized for the context's parameters
+    blocks: Vec<Block>,
+}
+
+impl<'key> Argon2WithMemory<'key> {
+    /// Allocate memory for hashing with the given Argon2 context.
+    pub fn new(argon2: Argon2<'key>) -> Result<Self> {
+        argon2.params().checked_memory_bytes()?;
+
+        #[cfg(feature = "std")]
+        crate::policy::check_global_max_memory(argon2.memory_bytes())?;
+
+        let blocks = vec![Block::default(); argon2.params().block_count()];
+        Ok(Self { argon2, blocks })
+    }
+
+    /// Get the Argon2 context.
+    pub fn argon2(&self) -> &Argon2<'key> {
+        &self.argon2
+    }
+
+    /// Hash a password and associated parameters into the provided output
+    /// buffer, reusing this context's memory.
+    ///
+    /// When the `zeroize` feature is enabled, the memory is zeroized after
+    /// hashing.
+    pub fn hash_password_reuse(&mut self, pwd: &[u8], salt: &[u8], out: &mut [u8]) -> Result<()> {
+        self.argon2
+            .hash_password_into_with_memory(pwd, salt, out, &mut self.blocks)
+    }
+}
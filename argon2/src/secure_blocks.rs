@@ -0,0 +1,131 @@
+//! Memory blocks which are locked into RAM.
+
+#![allow(unsafe_code)]
+
+use crate::{Block, Error, Params, Result};
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+/// Memory blocks which are locked into RAM, so they can't be paged to disk
+/// (e.g. swap or a hibernation file), and zeroized when dropped.
+///
+/// This uses `mlock` on Unix and `VirtualLock` on Windows. It can be passed
+/// to [`Argon2::hash_password_into_with_memory`][`crate::Argon2::hash_password_into_with_memory`].
+#[cfg_attr(docsrs, doc(cfg(feature = "secure-alloc")))]
+pub struct SecureBlocks {
+    /// Memory blocks
+    blocks: Vec<Block>,
+}
+
+impl SecureBlocks {
+    /// Allocate and lock the memory blocks required by the given [`Params`].
+    ///
+    /// Returns [`Error::MemoryLockFailed`] if the memory couldn't be locked,
+    /// e.g. because it exceeds the `RLIMIT_MEMLOCK` resource limit.
+    pub fn new(params: &Params) -> Result<Self> {
+        params.checked_memory_bytes()?;
+        crate::policy::check_global_max_memory(params.memory_bytes())?;
+
+        let blocks = vec![Block::default(); params.block_count()];
+
+        if !blocks.is_empty() && !lock(&blocks) {
+            return Err(Error::MemoryLockFailed);
+        }
+
+        Ok(Self { blocks })
+    }
+}
+
+impl AsRef<[Block]> for SecureBlocks {
+    fn as_ref(&self) -> &[Block] {
+        &self.blocks
+    }
+}
+
+impl AsMut<[Block]> for SecureBlocks {
+    fn as_mut(&mut self) -> &mut [Block] {
+        &mut self.blocks
+    }
+}
+
+impl Drop for SecureBlocks {
+    fn drop(&mut self) {
+        for block in self.blocks.iter_mut() {
+            block.zeroize();
+        }
+
+        if !self.blocks.is_empty() {
+            unlock(&self.blocks);
+        }
+    }
+}
+
+/// Lock the memory of the given blocks, returning whether it succeeded.
+#[cfg(unix)]
+fn lock(blocks: &[Block]) -> bool {
+    // SAFETY: the pointer and length describe a single allocation
+    unsafe { libc::mlock(blocks.as_ptr().cast(), blocks.len() * Block::SIZE) == 0 }
+}
+
+/// Unlock the memory of the given blocks.
+#[cfg(unix)]
+fn unlock(blocks: &[Block]) {
+    // SAFETY: the pointer and length describe a single allocation
+    unsafe {
+        libc::munlock(blocks.as_ptr().cast(), blocks.len() * Block::SIZE);
+    }
+}
+
+/// Lock the memory of the given blocks, returning whether it succeeded.
+#[cfg(windows)]
+fn lock(blocks: &[Block]) -> bool {
+    use windows_sys::Win32::System::Memory::VirtualLock;
+
+    // SAFETY: the pointer and length describe a single allocation
+    unsafe { VirtualLock(blocks.as_ptr() as *const _, blocks.len() * Block::SIZE) != 0 }
+}
+
+/// Unlock the memory of the given blocks.
+#[cfg(windows)]
+fn unlock(blocks: &[Block]) {
+    use windows_sys::Win32::System::Memory::VirtualUnlock;
+
+    // SAFETY: the pointer and length describe a single allocation
+    unsafe {
+        VirtualUnlock(blocks.as_ptr() as *const _, blocks.len() * Block::SIZE);
+    }
+}
+
+/// Memory locking isn't supported on this platform.
+#[cfg(not(any(unix, windows)))]
+fn lock(_blocks: &[Block]) -> bool {
+    false
+}
+
+/// Memory locking isn't supported on this platform.
+#[cfg(not(any(unix, windows)))]
+fn unlock(_blocks: &[Block]) {}
+
+#[cfg(test)]
+mod tests {
+    use super::SecureBlocks;
+    use crate::{Algorithm, Argon2, Params, Version};
+
+    #[test]
+    fn hash_with_secure_blocks() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone());
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(b"password", b"somesalt", &mut expected)
+            .unwrap();
+
+        let mut blocks = SecureBlocks::new(&params).unwrap();
+        let mut out = [0u8; 32];
+        argon2
+            .hash_password_into_with_memory(b"password", b"somesalt", &mut out, &mut blocks)
+            .unwrap();
+        assert_eq!(out, expected);
+    }
+}
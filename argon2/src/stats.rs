@@ -0,0 +1,167 @@
+//! Diagnostic statistics about the memory access pattern.
+
+use crate::{instance::Instance, Argon2, Block, Memory, Result};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Histogram of the reference blocks used while filling memory.
+///
+/// For every memory block, this counts how many times it was used as the
+/// reference block when computing a new block, accumulated over all hashes
+/// computed with [`Argon2::hash_password_into_with_stats`]. This can be used
+/// to compare the access patterns of the variants, e.g. by the number of
+/// distinct blocks referenced.
+///
+/// The counters are reset when used with a different number of memory
+/// blocks than before.
+///
+/// With [`Algorithm::Argon2d`][`crate::Algorithm::Argon2d`] and
+/// [`Algorithm::Argon2id`][`crate::Algorithm::Argon2id`] the access pattern
+/// depends on the password, so the statistics must be protected like it.
+#[derive(Clone, Default)]
+#[cfg_attr(docsrs, doc(cfg(feature = "stats")))]
+pub struct AccessStats {
+    /// Number of references to every memory block
+    references: Vec<u64>,
+}
+
+impl AccessStats {
+    /// Create empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of times every memory block has been referenced, indexed by
+    /// its position in memory.
+    pub fn references(&self) -> &[u64] {
+        &self.references
+    }
+
+    /// Number of distinct memory blocks which have been referenced.
+    pub fn distinct_blocks(&self) -> usize {
+        self.references.iter().filter(|&&count| count > 0).count()
+    }
+
+    /// Total number of references to all memory blocks.
+    pub fn total_references(&self) -> u64 {
+        self.references.iter().sum()
+    }
+
+    /// Reset all counters.
+    pub fn clear(&mut self) {
+        self.references.clear();
+    }
+
+    /// Prepare the counters for `block_count` memory blocks, resetting them
+    /// if they were used with a different number of blocks.
+    fn prepare(&mut self, block_count: usize) {
+        if self.references.len() != block_count {
+            self.references.clear();
+            self.references.resize(block_count, 0);
+        }
+    }
+
+    /// Record a reference to the memory block at `index`.
+    pub(crate) fn record(&mut self, index: usize) {
+        self.references[index] += 1;
+    }
+}
+
+impl fmt::Debug for AccessStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessStats")
+            .field("blocks", &self.references.len())
+            .field("distinct_blocks", &self.distinct_blocks())
+            .field("total_references", &self.total_references())
+            .finish()
+    }
+}
+
+impl Argon2<'_> {
+    /// Hash a password and associated parameters into the provided output
+    /// buffer, recording the reference blocks into `stats`.
+    ///
+    /// The output is the same as with [`Argon2::hash_password_into`]. The
+    /// lanes are always filled on the calling thread.
+    #[cfg_attr(docsrs, doc(cfg(feature = "stats")))]
+    pub fn hash_password_into_with_stats(
+        &self,
+        pwd: &[u8],
+        salt: &[u8],
+        out: &mut [u8],
+        stats: &mut AccessStats,
+    ) -> Result<()> {
+        self.validate_inputs(pwd.len(), salt, out.len())?;
+        self.params.checked_memory_bytes()?;
+
+        #[cfg(feature = "std")]
+        crate::policy::check_global_max_memory(self.params.memory_bytes())?;
+
+        let mut blocks = vec![Block::default(); self.params.block_count()];
+        stats.prepare(blocks.len());
+
+        let initial_hash = self.initial_hash(pwd, salt, out.len());
+        let memory = Memory::new(&mut blocks, self.params.segment_length());
+        Instance::hash_with_stats(self, initial_hash, memory, stats, |blockhash| {
+            crate::blake2b_long(&[blockhash], out)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AccessStats;
+    use crate::{Algorithm, Argon2, Params, Version};
+
+    #[test]
+    fn matches_regular_hashing() {
+        let params = Params::new(64, 2, 2, None).unwrap();
+
+        for alg in Algorithm::all() {
+            let argon2 = Argon2::new(alg, Version::V0x13, params.clone());
+            let mut expected = [0u8; 32];
+            argon2
+                .hash_password_into(b"password", b"somesalt", &mut expected)
+                .unwrap();
+
+            let mut stats = AccessStats::new();
+            let mut out = [0u8; 32];
+            argon2
+                .hash_password_into_with_stats(b"password", b"somesalt", &mut out, &mut stats)
+                .unwrap();
+            assert_eq!(out, expected);
+
+            // Every block but the first two of each lane is computed from
+            // one reference block
+            assert_eq!(stats.references().len(), 64);
+            assert_eq!(stats.total_references(), 2 * 64 - 2 * 2);
+            assert!(stats.distinct_blocks() > 0);
+            assert!(stats.distinct_blocks() <= 64);
+        }
+    }
+
+    #[test]
+    fn accumulates_and_resets() {
+        let argon2 = Argon2::from(Params::new(32, 1, 1, None).unwrap());
+        let mut stats = AccessStats::new();
+        let mut out = [0u8; 32];
+
+        for _ in 0..2 {
+            argon2
+                .hash_password_into_with_stats(b"password", b"somesalt", &mut out, &mut stats)
+                .unwrap();
+        }
+        assert_eq!(stats.total_references(), 2 * 30);
+
+        let argon2 = Argon2::from(Params::new(64, 1, 1, None).unwrap());
+        argon2
+            .hash_password_into_with_stats(b"password", b"somesalt", &mut out, &mut stats)
+            .unwrap();
+        assert_eq!(stats.references().len(), 64);
+        assert_eq!(stats.total_references(), 62);
+
+        stats.clear();
+        assert_eq!(stats.total_references(), 0);
+        assert_eq!(stats.distinct_blocks(), 0);
+    }
+}
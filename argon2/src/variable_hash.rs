@@ -0,0 +1,167 @@
+//! Variable-length hash function `H'`
+
+use crate::{Error, Params, Result};
+use blake2::{
+    digest::{self, Digest, VariableOutput},
+    Blake2b512, Blake2bVar,
+};
+
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+/// Output size of BLAKE2b in bytes
+const BLAKE2B_OUTBYTES: usize = 64;
+
+/// Variable-length hash function `H'` from the Argon2 specification, i.e.
+/// BLAKE2b with an extended output, as described in RFC 9106 section 3.3.
+///
+/// `inputs` are concatenated and hashed, prefixed with the output length,
+/// into `out`, which must be between [`Params::MIN_OUTPUT_LEN`] and
+/// [`Params::MAX_OUTPUT_LEN`] bytes long. Outputs up to 64 bytes are a
+/// single BLAKE2b hash; longer outputs are built from a chain of hashes.
+pub fn blake2b_long(inputs: &[&[u8]], out: &mut [u8]) -> Result<()> {
+    let mut pos = 0;
+
+    blake2b_long_chunks(inputs, out.len(), |chunk| {
+        out[pos..][..chunk.len()].copy_from_slice(chunk);
+        pos += chunk.len();
+    })
+}
+
+/// BLAKE2b with an extended output of `out_len` bytes, passing the output to
+/// `emit` in consecutive chunks of at most [`BLAKE2B_OUTBYTES`] bytes rather
+/// than writing it to a buffer.
+pub(crate) fn blake2b_long_chunks(
+    inputs: &[&[u8]],
+    out_len: usize,
+    mut emit: impl FnMut(&[u8]),
+) -> Result<()> {
+    if out_len < Params::MIN_OUTPUT_LEN as usize {
+        return Err(Error::OutputTooShort);
+    }
+
+    if out_len > Params::MAX_OUTPUT_LEN as usize {
+        return Err(Error::OutputTooLong);
+    }
+
+    let outlen_bytes = (out_len as u32).to_le_bytes();
+    let mut buf = [0u8; BLAKE2B_OUTBYTES];
+
+    if out_len <= BLAKE2B_OUTBYTES {
+        use digest::Update;
+
+        let mut digest = Blake2bVar::new(out_len).expect("`out_len` is valid for Blake2bVar");
+        Update::update(&mut digest, &outlen_bytes);
+
+        for input in inputs {
+            Update::update(&mut digest, input);
+        }
+
+        digest
+            .finalize_variable(&mut buf[..out_len])
+            .expect("`out_len` is valid for Blake2bVar");
+        emit(&buf[..out_len]);
+    } else {
+        let mut digest = Blake2b512::new();
+        digest.update(&outlen_bytes);
+
+        for input in inputs {
+            digest.update(input);
+        }
+
+        let mut hash = digest.finalize();
+
+        let n = BLAKE2B_OUTBYTES / 2;
+        let mut remaining = out_len - n;
+        emit(&hash[..n]);
+
+        while remaining > BLAKE2B_OUTBYTES {
+            hash = Blake2b512::digest(&hash);
+            emit(&hash[..n]);
+            remaining -= n;
+        }
+
+        Blake2bVar::digest_variable(&hash, &mut buf[..remaining])
+            .expect("`remaining` is valid for Blake2bVar");
+        emit(&buf[..remaining]);
+    }
+
+    #[cfg(feature = "zeroize")]
+    buf.zeroize();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::blake2b_long;
+    use crate::Error;
+    use blake2::{
+        digest::{consts::U32, Digest, VariableOutput},
+        Blake2b, Blake2b512, Blake2bVar,
+    };
+
+    #[test]
+    fn short_output() {
+        let mut out = [0u8; 32];
+        blake2b_long(&[b"foo", b"bar"], &mut out).unwrap();
+
+        let expected = Blake2b::<U32>::new()
+            .chain_update(32u32.to_le_bytes())
+            .chain_update(b"foobar")
+            .finalize();
+        assert_eq!(out[..], expected[..]);
+    }
+
+    #[test]
+    fn long_output() {
+        let mut out = [0u8; 100];
+        blake2b_long(&[b"foobar"], &mut out).unwrap();
+
+        let v1 = Blake2b512::new()
+            .chain_update(100u32.to_le_bytes())
+            .chain_update(b"foobar")
+            .finalize();
+        let v2 = Blake2b512::digest(&v1);
+        assert_eq!(out[..32], v1[..32]);
+        assert_eq!(out[32..64], v2[..32]);
+    }
+
+    /// Multi-kilobyte output, compared with the construction of RFC 9106
+    /// section 3.3: the first half of each hash in the chain, followed by
+    /// the whole last hash.
+    #[test]
+    fn multi_kilobyte_output() {
+        const OUT_LEN: usize = 4099;
+        let mut out = [0u8; OUT_LEN];
+        blake2b_long(&[b"foo", b"bar"], &mut out).unwrap();
+
+        let r = (OUT_LEN + 31) / 32 - 2;
+        let mut v = Blake2b512::new()
+            .chain_update((OUT_LEN as u32).to_le_bytes())
+            .chain_update(b"foobar")
+            .finalize();
+        let mut expected = v[..32].to_vec();
+
+        for _ in 1..r {
+            v = Blake2b512::digest(&v);
+            expected.extend_from_slice(&v[..32]);
+        }
+
+        let mut last = [0u8; 64];
+        let last = &mut last[..OUT_LEN - 32 * r];
+        Blake2bVar::digest_variable(&v, last).unwrap();
+        expected.extend_from_slice(last);
+
+        assert_eq!(out[..], expected[..]);
+    }
+
+    #[test]
+    fn output_len_bounds() {
+        assert_eq!(
+            blake2b_long(&[b"foobar"], &mut [0u8; 3]),
+            Err(Error::OutputTooShort)
+        );
+        assert!(blake2b_long(&[b"foobar"], &mut [0u8; 4]).is_ok());
+    }
+}
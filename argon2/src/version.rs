@@ -1,9 +1,16 @@
 //! Version of the algorithm.
 
 use crate::{Error, Result};
+use core::{
+    fmt::{self, Display},
+    str::FromStr,
+};
 
 /// Version of the algorithm.
-#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+///
+/// Versions are ordered by their version number, i.e. older versions are
+/// less than newer ones.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum Version {
     /// Version 16 (0x10 in hex)
@@ -18,9 +25,19 @@ pub enum Version {
 }
 
 impl Version {
+    /// Get all supported [`Version`]s, from oldest to newest.
+    pub const fn all() -> [Self; 2] {
+        [Version::V0x10, Version::V0x13]
+    }
+
+    /// Get the version number, i.e. `0x10` or `0x13`.
+    pub const fn as_u32(&self) -> u32 {
+        *self as u32
+    }
+
     /// Serialize version as little endian bytes
     pub(crate) fn to_le_bytes(self) -> [u8; 4] {
-        (self as u32).to_le_bytes()
+        self.as_u32().to_le_bytes()
     }
 }
 
@@ -32,7 +49,7 @@ impl Default for Version {
 
 impl From<Version> for u32 {
     fn from(version: Version) -> u32 {
-        version as u32
+        version.as_u32()
     }
 }
 
@@ -43,7 +60,116 @@ impl TryFrom<u32> for Version {
         match version_id {
             0x10 => Ok(Version::V0x10),
             0x13 => Ok(Version::V0x13),
-            _ => Err(Error::VersionInvalid),
+            _ => Err(Error::VersionUnsupported(version_id)),
+        }
+    }
+}
+
+impl Display for Version {
+    /// Format the version as its hexadecimal number, e.g. `0x13`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", u32::from(*self))
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    /// Parse a [`Version`] from its decimal (e.g. `19`) or hexadecimal
+    /// (e.g. `0x13`) number.
+    ///
+    /// As with [`Version::try_from`], a well-formed number which isn't a
+    /// supported version returns [`Error::VersionUnsupported`], and anything
+    /// else returns [`Error::VersionInvalid`].
+    fn from_str(s: &str) -> Result<Version> {
+        match s {
+            "16" | "0x10" => Ok(Version::V0x10),
+            "19" | "0x13" => Ok(Version::V0x13),
+            _ => {
+                let number = match s.strip_prefix("0x") {
+                    Some(hex) if is_number(hex, u8::is_ascii_hexdigit) => {
+                        u32::from_str_radix(hex, 16)
+                    }
+                    None if is_number(s, u8::is_ascii_digit) => s.parse(),
+                    _ => return Err(Error::VersionInvalid),
+                };
+
+                Err(number.map_or(Error::VersionInvalid, Error::VersionUnsupported))
+            }
+        }
+    }
+}
+
+/// Is `s` a non-empty sequence of digits accepted by `is_digit`?
+fn is_number(s: &str, is_digit: fn(&u8) -> bool) -> bool {
+    !s.is_empty() && s.bytes().all(|b| is_digit(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Version;
+    use crate::Error;
+
+    #[test]
+    fn all() {
+        assert_eq!(Version::all(), [Version::V0x10, Version::V0x13]);
+
+        for version in Version::all() {
+            assert_eq!(Version::try_from(u32::from(version)), Ok(version));
+        }
+
+        assert_eq!(
+            Version::try_from(0x12),
+            Err(Error::VersionUnsupported(0x12))
+        );
+    }
+
+    #[test]
+    fn ord() {
+        assert!(Version::V0x10 < Version::V0x13);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hash() {
+        let set: std::collections::HashSet<_> =
+            Version::all().into_iter().chain(Version::all()).collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn as_u32() {
+        assert_eq!(Version::V0x10.as_u32(), 0x10);
+        assert_eq!(Version::V0x13.as_u32(), 0x13);
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("16".parse(), Ok(Version::V0x10));
+        assert_eq!("0x10".parse(), Ok(Version::V0x10));
+        assert_eq!("19".parse(), Ok(Version::V0x13));
+        assert_eq!("0x13".parse(), Ok(Version::V0x13));
+        assert_eq!("13".parse::<Version>(), Err(Error::VersionUnsupported(13)));
+        assert_eq!(
+            "0x16".parse::<Version>(),
+            Err(Error::VersionUnsupported(0x16))
+        );
+        assert_eq!("0x".parse::<Version>(), Err(Error::VersionInvalid));
+        assert_eq!("+16".parse::<Version>(), Err(Error::VersionInvalid));
+        assert_eq!("v19".parse::<Version>(), Err(Error::VersionInvalid));
+        assert_eq!("99999999999".parse::<Version>(), Err(Error::VersionInvalid));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn display() {
+        use alloc::string::ToString;
+
+        for version in Version::all() {
+            assert_eq!(version.to_string().parse(), Ok(version));
         }
+
+        assert_eq!(Version::V0x10.to_string(), "0x10");
+        assert_eq!(Version::V0x13.to_string(), "0x13");
     }
 }
@@ -0,0 +1,85 @@
+//! Memory blocks which are zeroized when dropped.
+
+use crate::{Block, Params, Result};
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+/// Owned memory blocks which are zeroized when dropped.
+///
+/// It can be passed to
+/// [`Argon2::hash_password_into_with_memory`][`crate::Argon2::hash_password_into_with_memory`]
+/// so the memory doesn't have to be wiped manually once it's no longer
+/// needed.
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+pub struct ZeroizingBlocks {
+    /// Memory blocks
+    blocks: Vec<Block>,
+}
+
+impl ZeroizingBlocks {
+    /// Allocate the memory blocks required by the given [`Params`].
+    pub fn new(params: &Params) -> Result<Self> {
+        params.checked_memory_bytes()?;
+
+        #[cfg(feature = "std")]
+        crate::policy::check_global_max_memory(params.memory_bytes())?;
+
+        Ok(Self {
+            blocks: vec![Block::default(); params.block_count()],
+        })
+    }
+}
+
+impl From<Vec<Block>> for ZeroizingBlocks {
+    fn from(blocks: Vec<Block>) -> Self {
+        Self { blocks }
+    }
+}
+
+impl AsRef<[Block]> for ZeroizingBlocks {
+    fn as_ref(&self) -> &[Block] {
+        &self.blocks
+    }
+}
+
+impl AsMut<[Block]> for ZeroizingBlocks {
+    fn as_mut(&mut self) -> &mut [Block] {
+        &mut self.blocks
+    }
+}
+
+impl Drop for ZeroizingBlocks {
+    fn drop(&mut self) {
+        for block in self.blocks.iter_mut() {
+            block.zeroize();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ZeroizingBlocks;
+    use crate::{Algorithm, Argon2, Block, Params, Version};
+
+    #[test]
+    fn hash_with_zeroizing_blocks() {
+        let params = Params::new(32, 2, 1, None).unwrap();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone());
+
+        let mut expected = [0u8; 32];
+        argon2
+            .hash_password_into(b"password", b"somesalt", &mut expected)
+            .unwrap();
+
+        for mut blocks in [
+            ZeroizingBlocks::new(&params).unwrap(),
+            ZeroizingBlocks::from(vec![Block::default(); 64]),
+        ] {
+            let mut out = [0u8; 32];
+            argon2
+                .hash_password_into_with_memory(b"password", b"somesalt", &mut out, &mut blocks)
+                .unwrap();
+            assert_eq!(out, expected);
+        }
+    }
+}
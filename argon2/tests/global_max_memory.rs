@@ -0,0 +1,65 @@
+//! Tests for the process-wide memory ceiling.
+//!
+//! These live in their own test binary since the ceiling is global state
+//! shared by every test in the process.
+
+#![cfg(all(feature = "std", feature = "password-hash"))]
+
+use argon2::{
+    global_max_memory, set_global_max_memory, Argon2, Block, Error, Params, PasswordHash,
+    PasswordHasher, PasswordVerifier,
+};
+use password_hash::{errors::InvalidValue, SaltString};
+
+const PASSWORD: &[u8] = b"password";
+const SALT: &[u8] = b"somesalt";
+
+#[test]
+fn global_max_memory_is_enforced() {
+    let params = Params::new(64, 1, 1, None).unwrap();
+    let memory_bytes = params.block_count() * Block::SIZE;
+    let argon2 = Argon2::from(params);
+
+    let salt_string = SaltString::b64_encode(SALT).unwrap();
+    let hash_string = argon2
+        .hash_password(PASSWORD, &salt_string)
+        .unwrap()
+        .to_string();
+    let hash = PasswordHash::new(&hash_string).unwrap();
+    let mut out = [0u8; 32];
+
+    assert_eq!(global_max_memory(), None);
+
+    // Ceiling below the required memory
+    set_global_max_memory(memory_bytes - 1);
+    assert_eq!(global_max_memory(), Some(memory_bytes - 1));
+    assert_eq!(
+        argon2.hash_password_into(PASSWORD, SALT, &mut out),
+        Err(Error::MemoryExceedsPolicy)
+    );
+    assert_eq!(
+        argon2.hash_password(PASSWORD, &salt_string).map(|_| ()),
+        Err(InvalidValue::TooLong.param_error())
+    );
+    assert_eq!(
+        argon2.verify_password(PASSWORD, &hash),
+        Err(InvalidValue::TooLong.param_error())
+    );
+    assert_eq!(
+        argon2.verify_password_with_secrets(PASSWORD, &hash, &[b"pepper"]),
+        Err(Error::MemoryExceedsPolicy.into())
+    );
+
+    // Ceiling exactly at the required memory
+    set_global_max_memory(memory_bytes);
+    assert_eq!(argon2.hash_password_into(PASSWORD, SALT, &mut out), Ok(()));
+    assert_eq!(argon2.verify_password(PASSWORD, &hash), Ok(()));
+    // An empty secret is equivalent to no secret
+    assert_eq!(
+        argon2.verify_password_with_secrets(PASSWORD, &hash, &[b"pepper", &[]]),
+        Ok(())
+    );
+
+    set_global_max_memory(usize::MAX);
+    assert_eq!(global_max_memory(), None);
+}
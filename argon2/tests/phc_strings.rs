@@ -218,3 +218,29 @@ fn check_hash_encoding_parameters_order() {
     // The parameters shall appear in the m,t,p,keyid,data order
     assert_eq!(password_hash, "$argon2d$v=16$m=32,t=2,p=3,keyid=8PDw8A,data=Dw8PDw8P$AAAAAAAAAAA$KnH4gniiaFnDvlA1xev3yovC4cnrrI6tnHOYtmja90o");
 }
+
+#[test]
+fn verifies_hash_without_algorithm_prefix() {
+    let partial_hash =
+        "v=19$m=65536,t=2,p=1$c29tZXNhbHQ$CTFhFdXPJO1aFaMaO6Mm5c8y7cJHAph8ArZWb2GRPPc";
+    let argon2 = Argon2::default();
+
+    assert_eq!(
+        argon2.verify_str_with_algorithm(Algorithm::Argon2id, VALID_PASSWORD, partial_hash),
+        Ok(())
+    );
+    assert_eq!(
+        argon2.verify_str_with_algorithm(
+            Algorithm::Argon2id,
+            VALID_PASSWORD,
+            &format!("${}", partial_hash)
+        ),
+        Ok(())
+    );
+    assert!(argon2
+        .verify_str_with_algorithm(Algorithm::Argon2id, INVALID_PASSWORD, partial_hash)
+        .is_err());
+    assert!(argon2
+        .verify_str_with_algorithm(Algorithm::Argon2i, VALID_PASSWORD, partial_hash)
+        .is_err());
+}